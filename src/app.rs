@@ -1,16 +1,30 @@
+use std::collections::BTreeSet;
+
 use egui::{vec2, Align, Align2, ScrollArea, Vec2};
 
 use log::debug;
 use perhabs::{
     shared::asset_loader::{
         exercise_config_collection::ExerciseConfigCollection, perhabs_config::PerhabsConfig,
-        AssetSource,
+        programs::ProgramCollection, AssetSource,
     },
     shared::{self, egui_style, AppData},
     widgets,
-    wm::SessionManager,
     wm::Windows,
+    wm::{ExerciseType, SessionManager},
 };
+use serde::{Deserialize, Serialize};
+
+/// The app state that is restored on restart through eframe's persistence.
+/// Exercise configs and the anaglyph calibration are kept in the active profile,
+/// which is stored along with the training history.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+struct PersistedState {
+    dark_mode: bool,
+    open_windows: BTreeSet<String>,
+    selected_types: Vec<ExerciseType>,
+}
 
 pub struct Perhabs {
     tools: Windows,
@@ -19,6 +33,9 @@ pub struct Perhabs {
     show_about: bool,
     tts: tts::Tts,
     error: Option<String>,
+    new_profile_name: String,
+    /// Follows the visuals, so it can be saved without a context.
+    dark_mode: bool,
 }
 
 impl Default for Perhabs {
@@ -29,6 +46,8 @@ impl Default for Perhabs {
             appdata: AppData::default(),
             show_about: false,
             error: None,
+            new_profile_name: String::new(),
+            dark_mode: false,
 
             #[cfg(target_os = "macos")]
             tts: tts::Tts::new(tts::Backends::AppKit).unwrap(), // NOTE default is AvKit which is bugged(?)
@@ -48,10 +67,25 @@ impl Perhabs {
         // This is also where you can customized the look at feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
+        let mut app: Self = Default::default();
+
+        // Restore the state of the previous run, if any.
+        let state: PersistedState = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => PersistedState::default(),
+        };
+
         // Use custom styles
-        cc.egui_ctx.set_visuals(egui_style::light_visuals());
+        app.dark_mode = state.dark_mode;
+        match state.dark_mode {
+            true => cc.egui_ctx.set_visuals(egui_style::dark_visuals()),
+            false => cc.egui_ctx.set_visuals(egui_style::light_visuals()),
+        }
+        app.tools.open = state.open_windows;
+        app.sessionman.selected_types = state.selected_types;
+        app.sessionman.update_selected_sessions();
 
-        Default::default()
+        app
     }
 
     /// Only returns true when both PerhabsConfig and ExcerciseConfig are present.
@@ -198,6 +232,72 @@ impl Perhabs {
         // knows there is no config (yet).
         false
     }
+
+    /// Tries to load the training programs. Unlike the configs, programs are optional:
+    /// the app doesn't wait for them.
+    fn guarantee_programs(&mut self) {
+        if self.appdata.programs.is_some() {
+            return;
+        }
+
+        let config = match &self.appdata.config {
+            Some(res) => res,
+            None => return,
+        };
+
+        // Is there a promise for a web download of the programs?
+        match &self.appdata.programs_promise {
+            // No: try to get programs from disk.
+            // If that fails, put a promise in place for the next loop of this function
+            None => match ProgramCollection::from_disk(&format!(
+                "{}{}",
+                &config.disk_root, &config.programs_path,
+            )) {
+                Ok(mut res) => {
+                    res.source = AssetSource::Disk;
+                    self.appdata.programs = Some(res)
+                }
+                Err(e) => {
+                    let path = format!("{}{}", &config.web_root, &config.programs_path);
+                    self.appdata.programs_promise = Some(ProgramCollection::from_web(&path));
+                    self.appdata.debug_messages.push(format!(
+                        "Programs - No programs found on disk: {e}.\nGetting programs from {path}."
+                    ));
+                }
+            },
+            // Yes: we have a promise.
+            Some(promise) => match promise.ready() {
+                Some(Ok(resource)) => {
+                    debug!("Promise for programs is ready.");
+                    let programs =
+                        serde_json::from_str::<ProgramCollection>(resource.text().unwrap_or(""));
+
+                    // If deserialization fails, store hardcoded defaults.
+                    self.appdata.programs = match programs {
+                        Ok(mut res) => {
+                            res.source = AssetSource::Web;
+                            Some(res)
+                        }
+                        Err(error) => {
+                            self.appdata.debug_messages.push(format!(
+                                "Programs - Failed to deserialize programs: {}",
+                                error
+                            ));
+                            Some(ProgramCollection::default())
+                        }
+                    }
+                }
+                // Failing to download means there are no programs.
+                Some(Err(error)) => {
+                    self.appdata
+                        .debug_messages
+                        .push(format!("Programs - Failed to download programs: {}", error));
+                    self.appdata.programs = Some(ProgramCollection::default());
+                }
+                None => (),
+            },
+        }
+    }
 }
 
 // ***********
@@ -205,11 +305,33 @@ impl Perhabs {
 // ***********
 
 impl eframe::App for Perhabs {
+    /// Called by eframe to save the state before shutdown and every now and then.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let state = PersistedState {
+            dark_mode: self.dark_mode,
+            open_windows: self.tools.open.clone(),
+            selected_types: self.sessionman.selected_types.clone(),
+        };
+        eframe::set_value(storage, eframe::APP_KEY, &state);
+    }
+
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Show errors if there are any
         self.error_window(ctx);
 
+        // Keep track of the visuals so they can be saved
+        self.dark_mode = ctx.style().visuals.dark_mode;
+
+        // Keep track of the display, anaglyph calibrations are stored per display
+        self.appdata.display = shared::display_id(ctx);
+
+        // Store finished evaluations in the history
+        self.store_history();
+
+        // Store changes to the active profile
+        self.store_profile_changes();
+
         // Persistent menubar at the top of the screen.
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| self.menu_bar(ui, ctx));
 
@@ -222,14 +344,17 @@ impl eframe::App for Perhabs {
                 return;
             }
 
+            // Programs are loaded in the background
+            self.guarantee_programs();
+
             // Show about window
             self.about_screen(ctx);
 
             // Always show single windows
             self.tools.windows(ctx, &self.appdata, &mut self.tts);
 
-            // Show the session menu or an active session if present
-            if self.sessionman.open_session.is_some() {
+            // Show the session menu or an active session or program if present
+            if self.sessionman.is_active() {
                 self.sessionman
                     .session_show(ctx, &self.appdata, &mut self.tts);
                 return;
@@ -255,6 +380,92 @@ impl eframe::App for Perhabs {
 }
 
 impl Perhabs {
+    /// Move any newly finished evaluations into the history and save it.
+    fn store_history(&mut self) {
+        while let Ok(record) = self.appdata.history_rx.try_recv() {
+            if let Err(e) = self.appdata.history.add(record) {
+                self.appdata.debug_messages.push(format!("History - {}", e));
+                self.appdata.error_tx.send(e);
+            }
+        }
+    }
+
+    /// Apply any changes exercises made to the active profile and save them.
+    fn store_profile_changes(&mut self) {
+        while let Ok(change) = self.appdata.profile_rx.try_recv() {
+            if let Err(e) = self.appdata.profiles.apply(change) {
+                self.appdata
+                    .debug_messages
+                    .push(format!("Profiles - {}", e));
+                self.appdata.error_tx.send(e);
+            }
+        }
+    }
+
+    /// Make another profile active. Every session is reset, so no session
+    /// carries over settings or results from the previous profile.
+    fn switch_profile(&mut self, name: &str) {
+        if let Err(e) = self.appdata.profiles.select(name) {
+            self.appdata.error_tx.send(e);
+        }
+        self.appdata
+            .history
+            .set_profile(&self.appdata.profiles.active().name);
+        self.sessionman.reset_all();
+    }
+
+    /// Select, add and remove profiles.
+    fn profile_menu(&mut self, ui: &mut egui::Ui) {
+        let active = self.appdata.profiles.active().name.clone();
+
+        // Select a profile
+        let mut selected = None;
+        for name in self.appdata.profiles.names() {
+            if ui.selectable_label(name == active, name).clicked() {
+                selected = Some(name.to_owned());
+            }
+        }
+        if let Some(name) = selected {
+            if name != active {
+                self.switch_profile(&name);
+            }
+            ui.close_menu();
+        }
+        ui.separator();
+
+        // Add a profile and make it active
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_profile_name).desired_width(120.));
+            if ui.button("Add").clicked() {
+                let name = self.new_profile_name.trim().to_owned();
+                match self.appdata.profiles.add(&name) {
+                    Ok(_) => {
+                        self.new_profile_name.clear();
+                        self.switch_profile(&name);
+                        ui.close_menu();
+                    }
+                    Err(e) => {
+                        self.appdata.error_tx.send(e);
+                    }
+                }
+            }
+        });
+
+        // Remove the active profile. Its history is kept.
+        if ui.button(format!("Remove {}", active)).clicked() {
+            match self.appdata.profiles.remove(&active) {
+                Ok(_) => {
+                    let name = self.appdata.profiles.active().name.clone();
+                    self.switch_profile(&name);
+                }
+                Err(e) => {
+                    self.appdata.error_tx.send(e);
+                }
+            }
+            ui.close_menu();
+        }
+    }
+
     // Error window
     fn error_window(&mut self, ctx: &egui::Context) {
         // Check for error messages
@@ -310,20 +521,25 @@ impl Perhabs {
                 self.tools.labels(ui);
             });
 
+            // Profile menu
+            let profile_label = format!("\u{1F464} {}", self.appdata.profiles.active().name);
+            ui.menu_button(profile_label, |ui| self.profile_menu(ui));
+
             // About button
             ui.toggle_value(&mut self.show_about, "About");
 
             // Only show quit when a session is active.
-            if let Some(session_name) = self.sessionman.open_session {
-                ui.add_space(ui.available_width() - 85.);
+            if self.sessionman.open_session.is_some() {
+                ui.add_space(ui.available_width() - 170.);
+                let pause_label = match self.sessionman.paused {
+                    true => "\u{25B6} Resume",
+                    false => "\u{23F8} Pause",
+                };
+                if ui.button(pause_label).clicked() {
+                    self.sessionman.toggle_pause();
+                }
                 if ui.button("\u{2386} Quit session").clicked() {
-                    self.sessionman.open_session = None;
-                    // Reset the session on close
-                    for session in &mut self.sessionman.sessions {
-                        if session.name() == session_name {
-                            session.reset();
-                        }
-                    }
+                    self.sessionman.quit();
                 }
             }
         });
@@ -343,7 +559,7 @@ impl Perhabs {
             .movable(false)
             .collapsible(false)
             .show(ctx, |ui| {
-                self.sessionman.exercise_buttons_cols(ui);
+                self.sessionman.exercise_buttons_cols(ui, &self.appdata);
             });
     }
 
@@ -353,7 +569,7 @@ impl Perhabs {
         ui.add_space(10.);
         ScrollArea::new([false, true])
             .drag_to_scroll(true)
-            .show(ui, |ui| self.sessionman.exercise_buttons(ui));
+            .show(ui, |ui| self.sessionman.exercise_buttons(ui, &self.appdata));
     }
 
     fn about_screen(&mut self, ctx: &egui::Context) {
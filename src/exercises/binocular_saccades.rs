@@ -1,9 +1,10 @@
 use std::ops::Neg;
 
+use crate::exercises::shared::arrow_trial::ArrowTrial;
 use crate::exercises::Direction;
-use crate::shared::asset_loader::exercise_config::vergence::VergenceConfig;
+use crate::shared::asset_loader::exercise_config::{find_config, vergence::VergenceConfig};
 use crate::shared::Anaglyph;
-use crate::shared::AppData;
+use crate::shared::{AppData, ProfileChange};
 use crate::widgets::evaluation::eval_config_widgets;
 use crate::widgets::exercise_config_menu::exercise_config_menu;
 use crate::widgets::{self};
@@ -18,15 +19,16 @@ use rand::{seq::SliceRandom, Rng};
 
 use super::ExerciseStage;
 
-#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct BinoSaccades {
     session_status: ExerciseStage,
     anaglyph: Anaglyph,
     anaglyph_pos: Option<Pos2>,
     offset_variation: isize,
+    config_name: String,
     answer: Option<Direction>, // The right answer is the direction of the arrow
     response: Option<Direction>, // The given response is a direction
     answer_timeout_timer: Timer,
+    trials: Vec<ArrowTrial>, // Every arrow shown and the response to it
     answer_timeout_ms: i64,
     evaluation: Evaluation<f32>,
 }
@@ -38,9 +40,11 @@ impl Default for BinoSaccades {
             anaglyph: Anaglyph::default(),
             anaglyph_pos: None,
             offset_variation: 0,
+            config_name: String::new(),
             answer: None,
             response: None,
             answer_timeout_timer: Timer::new(),
+            trials: vec![],
             answer_timeout_ms: 1000,
             evaluation: Evaluation::new(Duration::try_seconds(60).unwrap_or_default(), 60),
         }
@@ -65,7 +69,7 @@ impl BinoSaccades {
     /// - the position of the anaglyph on the screen
     /// - the offset of the two parts of the anaglyph
     fn new_anaglyph_params(&mut self) {
-        let mut rng = rand::thread_rng();
+        let rng = self.evaluation.rng();
 
         // Pick a direction for the diamond
         if let Some(direction) = vec![
@@ -74,7 +78,7 @@ impl BinoSaccades {
             Direction::Up,
             Direction::Down,
         ]
-        .choose(&mut rng)
+        .choose(rng)
         {
             self.answer = Some(*direction);
             self.anaglyph.focal_position = *direction;
@@ -122,7 +126,8 @@ impl BinoSaccades {
                 // If no anaglyph is visible, create new anaglyph and set answer timeout timer
                 if let None = self.answer {
                     self.new_anaglyph_params();
-                    self.anaglyph.initialize();
+                    self.anaglyph.initialize(self.evaluation.rng());
+                    self.evaluation.set_stimulus_onset();
                     self.answer_timeout_timer.set(
                         Duration::try_milliseconds(self.answer_timeout_ms).unwrap_or_default(),
                     );
@@ -143,7 +148,26 @@ impl BinoSaccades {
     }
 
     fn next(&mut self) {
-        self.evaluation.add_result(self.evaluate_response());
+        if let (Some(pos), Some(answer)) = (self.anaglyph_pos, self.answer) {
+            self.trials.push(ArrowTrial {
+                pos,
+                answer,
+                response: self.response,
+            });
+        }
+        self.evaluation.set_trial_details(
+            serde_json::json!({
+                "position": self.anaglyph_pos,
+                "focal_position": self.anaglyph.focal_position,
+                "background_offset": self.anaglyph.background_offset,
+            }),
+            self.response,
+        );
+        let result = self.evaluate_response();
+        match self.response {
+            Some(_) => self.evaluation.add_result(result),
+            None => self.evaluation.add_missed_result(result),
+        }
         self.answer = None;
         self.response = None;
     }
@@ -211,7 +235,16 @@ impl BinoSaccades {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) = self
+            .evaluation
+            .take_record(self.name(), &self.config_name, score)
+        {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -219,6 +252,24 @@ impl BinoSaccades {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        widgets::evaluation::trial_review(
+            ui,
+            vec2(160., 60.),
+            &self.trials,
+            &self.evaluation.scores(),
+            self.evaluation.show_timings(),
+            |painter, to_screen, trial| trial.paint(painter, to_screen),
+        );
+
+        widgets::evaluation::reaction_time_widgets(
+            ui,
+            self.evaluation.reaction_time_mean(),
+            self.evaluation.reaction_time_median(),
+            self.evaluation.reaction_time_variance(),
+        );
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -247,6 +298,48 @@ impl Exercise for BinoSaccades {
         vec![ExerciseType::Visual]
     }
 
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        let exercise = appdata
+            .excconfig
+            .as_ref()
+            .and_then(|c| find_config(&c.divergence, config))
+            .ok_or_else(|| format!("{} has no config named {}.", self.name(), config))?;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.anaglyph.color = appdata.anaglyph_color();
+        self.anaglyph.initialize(self.evaluation.rng());
+        self.anaglyph.pixel_size = exercise.pixel_size;
+        self.offset_variation = exercise.step;
+        self.config_name = exercise.name.to_owned();
+        self.session_status = ExerciseStage::Response;
+        self.evaluation.start();
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.session_status == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.answer_timeout_timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.answer_timeout_timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut tts::Tts) {
         let menu_window = egui::Window::new(self.name())
             .anchor(
@@ -265,7 +358,7 @@ impl Exercise for BinoSaccades {
             }
             // After an evaluation show the review
             ExerciseStage::Finished => {
-                menu_window.show(ctx, |ui| self.finished_screen(ui));
+                menu_window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             // Any other status means we are in session.
             _ => {
@@ -278,6 +371,9 @@ impl Exercise for BinoSaccades {
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut tts::Tts) {
+        // Use the anaglyph calibration of the active profile.
+        self.anaglyph.color = appdata.anaglyph_color();
+
         ui.heading("Explanation");
         ui.label(self.help());
         ui.separator();
@@ -290,6 +386,7 @@ impl Exercise for BinoSaccades {
             [30, 120],
             [30, 120],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
         // Some buttons to set the response time limit
         ui.horizontal(|ui| {
@@ -318,17 +415,25 @@ impl Exercise for BinoSaccades {
         ui.separator();
 
         // Display all exercise configs
+        let preferred = appdata.profiles.active().config(self.name());
         let mut func = |exercise: &VergenceConfig| {
-            self.anaglyph.initialize();
+            // Remember the chosen config for the active profile.
+            let _ = appdata.profile_tx.send(ProfileChange::Config {
+                exercise: self.name().to_owned(),
+                config: exercise.name.to_owned(),
+            });
+            self.anaglyph.initialize(self.evaluation.rng());
             self.anaglyph.pixel_size = exercise.pixel_size;
             self.offset_variation = exercise.step;
+            self.config_name = exercise.name.to_owned();
             self.session_status = ExerciseStage::Response;
             self.evaluation.start();
         };
 
         // Display exercise configs
         if let Some(config) = &appdata.excconfig {
-            if let Some(config) = exercise_config_menu::<VergenceConfig>(ui, &config.divergence, 3)
+            if let Some(config) =
+                exercise_config_menu::<VergenceConfig>(ui, &config.divergence, 3, preferred)
             {
                 func(config)
             };
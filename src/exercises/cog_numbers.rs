@@ -2,12 +2,12 @@ use crate::shared::{AppData, Evaluation};
 use crate::widgets::{self, menu_button};
 use crate::wm::{Exercise, ExerciseType};
 use chrono::Duration;
-use egui::{vec2, Align, RichText, Vec2};
+use egui::{vec2, Align, Color32, Key, RichText, TextEdit, Vec2};
 use rand::prelude::*;
 
 use tts::{self, Tts};
 
-use super::{numvec_to_string, ExerciseStage};
+use super::{count_from_config, numvec_to_string, ExerciseStage};
 
 struct Answers {
     sequence: String,
@@ -26,12 +26,87 @@ impl Default for Answers {
     }
 }
 
+/// The shortest and longest sequences in recall mode.
+const MIN_SPAN: usize = 2;
+const MAX_SPAN: usize = 20;
+
+/// The order in which the numbers are typed back in recall mode.
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Recall {
+    Forward,
+    Backward,
+    Sorted,
+    ReverseSorted,
+}
+
+impl std::fmt::Display for Recall {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Recall::Forward => write!(f, "forward"),
+            Recall::Backward => write!(f, "backward"),
+            Recall::Sorted => write!(f, "sorted"),
+            Recall::ReverseSorted => write!(f, "reverse sorted"),
+        }
+    }
+}
+
+impl Recall {
+    const ALL: [Recall; 4] = [
+        Recall::Forward,
+        Recall::Backward,
+        Recall::Sorted,
+        Recall::ReverseSorted,
+    ];
+
+    /// The sequence in the order it should be typed back.
+    fn order(&self, sequence: &[u32]) -> Vec<u32> {
+        let mut ordered = sequence.to_vec();
+        match self {
+            Recall::Forward => (),
+            Recall::Backward => ordered.reverse(),
+            Recall::Sorted => ordered.sort(),
+            Recall::ReverseSorted => ordered.sort_by(|a, b| b.cmp(a)),
+        }
+        ordered
+    }
+}
+
+/// A random sequence of numbers from 0 to 10. Numbers only repeat when the
+/// sequence is longer than the 11 numbers available, and never twice in a row.
+fn number_sequence<R: Rng + ?Sized>(rng: &mut R, length: usize) -> Vec<u32> {
+    let mut seq = vec![];
+    while seq.len() < length {
+        let num = rng.gen_range(0..=10);
+        let allowed = match length > 11 {
+            true => seq.last() != Some(&num),
+            false => !seq.contains(&num),
+        };
+        if allowed {
+            seq.push(num);
+        };
+    }
+    seq
+}
+
+/// Read the numbers from a typed answer. Anything but digits separates numbers.
+fn parse_numbers(answer: &str) -> Vec<u32> {
+    answer
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
 /// Sequences
 pub struct CogNumbers {
     seq_length: usize,
+    /// In recall mode, the numbers are typed back and the length of the
+    /// sequence follows the answers. Without it, the answers are only shown.
+    recall: Option<Recall>,
+    sequence: Vec<u32>,
+    typed: String,
     session: ExerciseStage,
     answers: Answers,
-    evaluation: Evaluation<bool>,
+    evaluation: Evaluation<(usize, bool)>,
 }
 
 impl Default for CogNumbers {
@@ -39,6 +114,9 @@ impl Default for CogNumbers {
         Self {
             answers: Answers::default(),
             seq_length: 4,
+            recall: None,
+            sequence: vec![],
+            typed: String::new(),
             session: ExerciseStage::None,
             evaluation: Evaluation::new(Duration::try_seconds(240).unwrap_or_default(), 10),
         }
@@ -56,19 +134,44 @@ impl CogNumbers {
 
     fn next(&mut self, tts: &mut tts::Tts) {
         match self.session {
-            ExerciseStage::Challenge => {
-                self.evaluation.add_result(true);
-                self.session = ExerciseStage::Result;
-            }
+            ExerciseStage::Challenge => match self.recall {
+                Some(recall) => self.check_answer(recall),
+                None => {
+                    self.evaluation.set_trial_details(
+                        serde_json::json!({ "sequence": self.answers.sequence }),
+                        serde_json::Value::Null,
+                    );
+                    self.evaluation.add_result((self.seq_length, true));
+                    self.session = ExerciseStage::Result;
+                }
+            },
             ExerciseStage::Result => {
                 self.session = ExerciseStage::Challenge;
                 self.pick_sequence();
                 self.say(tts);
+                self.evaluation.set_stimulus_onset();
             }
             _ => (),
         }
     }
 
+    /// Score the typed answer. The next sequence is one number longer after a
+    /// correct answer and one number shorter after a mistake.
+    fn check_answer(&mut self, recall: Recall) {
+        let answer = parse_numbers(&self.typed);
+        let correct = answer == recall.order(&self.sequence);
+        self.evaluation.set_trial_details(
+            serde_json::json!({ "sequence": self.sequence, "order": recall.to_string() }),
+            &answer,
+        );
+        self.evaluation.add_result((self.seq_length, correct));
+        self.seq_length = match correct {
+            true => (self.seq_length + 1).min(MAX_SPAN),
+            false => self.seq_length.saturating_sub(1).max(MIN_SPAN),
+        };
+        self.session = ExerciseStage::Result;
+    }
+
     fn say(&mut self, tts: &mut tts::Tts) {
         match tts.speak(&self.answers.sequence, false) {
             Ok(_) => debug!("TTS: Sentence spoken."),
@@ -77,18 +180,10 @@ impl CogNumbers {
     }
 
     fn pick_sequence(&mut self) {
-        let mut seq = vec![];
-        if self.seq_length > 11 {
-            return;
-        }
-        let mut rng = thread_rng();
-        while seq.len() < self.seq_length {
-            // this means no seq longer than 11 numbers (0..10)!
-            let num = rng.gen_range(0..=10);
-            if !seq.contains(&num) {
-                seq.push(num);
-            };
-        }
+        let length = self.seq_length;
+        let mut seq = number_sequence(self.evaluation.rng(), length);
+        self.sequence = seq.clone();
+        self.typed.clear();
 
         self.answers.sequence = numvec_to_string(&seq);
         seq.reverse();
@@ -100,7 +195,17 @@ impl CogNumbers {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        let config = match self.recall {
+            Some(recall) => format!("{} numbers, {}", self.start_length(), recall),
+            None => format!("{} numbers", self.seq_length),
+        };
+        if let Some(record) = self.evaluation.take_record(self.name(), &config, score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -108,13 +213,34 @@ impl CogNumbers {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        if self.recall.is_some() {
+            ui.label(format!(
+                "Longest sequence: {} numbers",
+                self.evaluation.max_difficulty()
+            ));
+        }
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
         }
     }
 
+    /// The length of the first sequence of the evaluation.
+    fn start_length(&self) -> usize {
+        match self.evaluation.show_results().first() {
+            Some((length, _)) => *length,
+            None => self.seq_length,
+        }
+    }
+
     fn read_keypress(&mut self, ctx: &egui::Context, tts: &mut tts::Tts) {
+        // Space and return are part of the answer while typing.
+        if ctx.wants_keyboard_input() {
+            return;
+        }
         if ctx.input(|i| i.key_pressed(egui::Key::Space)) {
             self.next(tts)
         }
@@ -141,7 +267,9 @@ Each string of numbers will be shown in full, alongside with
 - the numbers ordered small to large
 - the numbers ordered large to small
 
-Try to rearrange the numbers and work your brain!"
+Try to rearrange the numbers and work your brain!
+
+In recall mode, you type the numbers back in the order you picked. The sequence gets one number longer after every correct answer and one number shorter after every mistake."
     }
 
     fn excercise_type(&self) -> Vec<ExerciseType> {
@@ -152,7 +280,48 @@ Try to rearrange the numbers and work your brain!"
         *self = Default::default();
     }
 
-    /// Show the configuration dialog
+    /// Configs are named after the number of items, e.g. "6 numbers".
+    /// Recall mode adds the order to type the numbers in, e.g. "6 numbers, backward".
+    fn start_with(
+        &mut self,
+        _: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        self.seq_length = count_from_config(config, 4..=10)?;
+        self.recall = match config.split_once(", ") {
+            Some((_, order)) => Some(
+                *Recall::ALL
+                    .iter()
+                    .find(|recall| recall.to_string() == order)
+                    .ok_or(format!("Config {} has an unknown order.", config))?,
+            ),
+            None => None,
+        };
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.evaluation.start();
+        self.session = ExerciseStage::Result;
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.session == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
         let window = egui::Window::new(self.name())
             .anchor(
@@ -169,7 +338,7 @@ Try to rearrange the numbers and work your brain!"
                 window.show(ctx, |ui| self.ui(ui, appdata, tts));
             }
             ExerciseStage::Finished => {
-                window.show(ctx, |ui| self.finished_screen(ui));
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             _ => {
                 self.read_keypress(ctx, tts);
@@ -182,6 +351,7 @@ Try to rearrange the numbers and work your brain!"
         if self.session != ExerciseStage::None {}
     }
 
+    /// Show the configuration dialog
     fn ui(&mut self, ui: &mut egui::Ui, _: &AppData, _: &mut Tts) {
         ui.label(self.help());
         ui.separator();
@@ -194,6 +364,15 @@ Try to rearrange the numbers and work your brain!"
             [30, 600],
             [5, 60],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
+        // Pick between showing the answers or typing them back
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.recall, None, "Show answers");
+            for recall in Recall::ALL {
+                ui.selectable_value(&mut self.recall, Some(recall), format!("Recall {}", recall));
+            }
+        });
 
         // Draw a menu in two columns
         let mut func = |i| {
@@ -241,7 +420,49 @@ Try to rearrange the numbers and work your brain!"
         });
 
         ui.vertical_centered(|ui| {
-            if self.session == ExerciseStage::Result {
+            // Recall mode: type back the numbers and see if they are right.
+            // Shows the start prompt before the first sequence.
+            let started = !self.evaluation.show_results().is_empty()
+                || self.session == ExerciseStage::Challenge;
+            if let (Some(recall), true) = (self.recall, started) {
+                ui.add_space(spacer * 4.);
+                match self.session {
+                    ExerciseStage::Challenge => {
+                        ui.label(format!("Type the numbers {}.", recall));
+                        ui.add_space(spacer);
+                        let input = ui.add(
+                            TextEdit::singleline(&mut self.typed)
+                                .font(egui::TextStyle::Heading)
+                                .desired_width(300.),
+                        );
+                        if input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                            self.check_answer(recall);
+                        }
+                        input.request_focus();
+                        ui.add_space(spacer * 7.);
+                    }
+                    _ => {
+                        let (text, color) = match self.evaluation.show_results().last() {
+                            Some((_, true)) => ("Correct!", Color32::GREEN),
+                            _ => ("Not quite.", Color32::RED),
+                        };
+                        ui.label(RichText::new(text).size(25.).color(color));
+                        ui.add_space(spacer);
+                        ui.label(format!("The numbers {}", recall));
+                        ui.heading(
+                            RichText::new(numvec_to_string(&recall.order(&self.sequence)))
+                                .size(25.),
+                        );
+                        ui.add_space(spacer);
+                        ui.label("Your answer");
+                        ui.label(
+                            RichText::new(numvec_to_string(&parse_numbers(&self.typed)))
+                                .size(25.),
+                        );
+                        ui.add_space(spacer * 3.);
+                    }
+                }
+            } else if self.session == ExerciseStage::Result {
                 ui.add_space(spacer * 4.);
 
                 ui.label("Sentence");
@@ -259,9 +480,8 @@ Try to rearrange the numbers and work your brain!"
                 ui.label("Alphabetical reversed");
                 ui.label(RichText::new(&self.answers.sequence_alpha_rev).size(25.));
                 ui.add_space(spacer);
-            };
 
-            if self.session == ExerciseStage::Challenge {
+            } else if self.session == ExerciseStage::Challenge {
                 ui.add_space(spacer * 4.);
                 ui.label("Try to reorder the numbers in your head.\nPress repeat (enter) to hear the numbers again.");
                 ui.add_space(spacer * 9.);
@@ -278,8 +498,12 @@ Try to rearrange the numbers and work your brain!"
 
             ui.add_space(spacer / 4.);
 
+            let next = match (self.recall, &self.session) {
+                (Some(_), ExerciseStage::Challenge) => "Check",
+                _ => "Next",
+            };
             if ui
-                .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Next"))
+                .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new(next))
                 .clicked()
             {
                 self.next(tts);
@@ -290,3 +514,32 @@ Try to rearrange the numbers and work your brain!"
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let seq = number_sequence(&mut rng, 11);
+        let mut unique = seq.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 11);
+
+        // Longer sequences repeat numbers, but never twice in a row.
+        let seq = number_sequence(&mut rng, MAX_SPAN);
+        assert_eq!(seq.len(), MAX_SPAN);
+        assert!(seq.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn recall_answers() {
+        let seq = [3, 10, 0, 7];
+        assert_eq!(Recall::Backward.order(&seq), vec![7, 0, 10, 3]);
+        assert_eq!(Recall::Sorted.order(&seq), vec![0, 3, 7, 10]);
+        assert_eq!(Recall::ReverseSorted.order(&seq), vec![10, 7, 3, 0]);
+        assert_eq!(parse_numbers("3, 10 0,7 "), seq.to_vec());
+    }
+}
@@ -1,10 +1,13 @@
 use crate::shared::asset_loader::sentences::{SentenceFile, Sentences};
-use crate::shared::{asset_loader, AppData};
-use crate::widgets::menu_button;
+use crate::shared::{asset_loader, AppData, ProfileChange};
+use crate::widgets::{self, menu_button};
 use crate::wm::{Exercise, ExerciseType};
 use egui::{vec2, Align, RichText, Vec2};
+use rand::prelude::*;
 use tts::{self, Tts};
 
+use super::{alphabetical, reversed};
+
 struct Answers {
     sequence: String,
     sequence_alpha: String,
@@ -28,6 +31,10 @@ pub struct CogWords {
     answers: Answers,
     session_active: bool,
     display_answer: bool,
+    /// The sentences are shuffled with a seeded rng, so a session can be replayed.
+    replay_seed: Option<u64>,
+    seed: u64,
+    rng: StdRng,
 }
 
 impl Default for CogWords {
@@ -37,6 +44,9 @@ impl Default for CogWords {
             sentences: Sentences::default(),
             session_active: false,
             display_answer: true,
+            replay_seed: None,
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
         }
     }
 }
@@ -84,13 +94,11 @@ impl CogWords {
                         })
                         .collect();
                     self.answers.sequence = answer.to_owned();
-                    let mut sorted: Vec<&str> = answer.split(" ").collect();
-                    sorted.reverse();
-                    self.answers.sequence_rev = sorted.join(" ");
-                    sorted.sort();
+                    let words: Vec<&str> = answer.split(" ").collect();
+                    self.answers.sequence_rev = reversed(&words).join(" ");
+                    let sorted = alphabetical(&words);
                     self.answers.sequence_alpha = sorted.join(" ");
-                    sorted.reverse();
-                    self.answers.sequence_alpha_rev = sorted.join(" ");
+                    self.answers.sequence_alpha_rev = reversed(&sorted).join(" ");
 
                     // Insert the answer at the start of the vec,
                     // like putting it at the bottom of a deck of cards
@@ -130,7 +138,7 @@ impl CogWords {
                         // Found contents: store in self and shuffle
                         Ok(file) => {
                             self.sentences.contents = Some(file);
-                            self.sentences.shuffle_contents()
+                            self.sentences.shuffle_contents(&mut self.rng)
                         }
                         // Can't load from disk: create a promise to load from web
                         Err(_) => {
@@ -162,7 +170,7 @@ impl CogWords {
                         };
 
                     // Finally, shuffle the downloaded/default contents
-                    self.sentences.shuffle_contents()
+                    self.sentences.shuffle_contents(&mut self.rng)
                 }
             }
         }
@@ -258,15 +266,25 @@ Pick your language and work your brain!"
         // An explanation of this exercise.
         ui.label(self.help());
         ui.separator();
+        widgets::evaluation::seed_widgets(ui, &mut self.replay_seed);
 
-        // Show language picker
+        // Show language picker, highlighting the language preferred by the active profile.
         // First define what happens when we click a language
+        let preferred = appdata.profiles.active().language.as_deref();
         let mut func = |file: &SentenceFile| {
+            // Remember the chosen language for the active profile.
+            let _ = appdata
+                .profile_tx
+                .send(ProfileChange::Language(file.language.to_owned()));
             // Select file
             self.sentences.selected_file = Some(file.to_owned());
             // Trigger content loading
             self.sentences.contents = None;
-            // Activate session
+            // Seed the shuffle and activate session
+            self.seed = self
+                .replay_seed
+                .unwrap_or_else(|| rand::random::<u32>() as u64);
+            self.rng = StdRng::seed_from_u64(self.seed);
             self.session_active = true;
         };
 
@@ -278,8 +296,18 @@ Pick your language and work your brain!"
                 // Column 1 gets populated with at least half the buttons
                 for i in 0..col_1_range as usize {
                     if let Some(file) = config.sentences_files.get(i) {
-                        if menu_button(&mut col[0], None, None, file.language.as_str(), "")
-                            .clicked()
+                        let override_color = match preferred == Some(file.language.as_str()) {
+                            true => Some(col[0].visuals().selection.bg_fill),
+                            false => None,
+                        };
+                        if menu_button(
+                            &mut col[0],
+                            None,
+                            override_color,
+                            file.language.as_str(),
+                            "",
+                        )
+                        .clicked()
                         {
                             func(file);
                         };
@@ -289,8 +317,18 @@ Pick your language and work your brain!"
                 // Column 2 gets populated with the remaining buttons
                 for i in col_1_range as usize..buttons_total as usize {
                     if let Some(file) = config.sentences_files.get(i) {
-                        if menu_button(&mut col[1], None, None, file.language.as_str(), "")
-                            .clicked()
+                        let override_color = match preferred == Some(file.language.as_str()) {
+                            true => Some(col[1].visuals().selection.bg_fill),
+                            false => None,
+                        };
+                        if menu_button(
+                            &mut col[1],
+                            None,
+                            override_color,
+                            file.language.as_str(),
+                            "",
+                        )
+                        .clicked()
                         {
                             func(file);
                         };
@@ -310,6 +348,7 @@ Pick your language and work your brain!"
             if ui.button("Close").clicked() {
                 self.reset()
             };
+            widgets::evaluation::seed_label(ui, self.seed);
         });
 
         ui.vertical_centered(|ui| {
@@ -3,10 +3,12 @@ use chrono::Duration;
 use egui::{Align, Key, Vec2};
 use tts::Tts;
 
-use crate::shared::asset_loader::exercise_config::depth_perception::DepthPerceptionConfig;
+use crate::shared::asset_loader::exercise_config::{
+    depth_perception::DepthPerceptionConfig, find_config,
+};
 
-use crate::shared::AppData;
 use crate::shared::Evaluation;
+use crate::shared::{AppData, ProfileChange};
 use crate::widgets;
 use crate::widgets::evaluation::eval_config_widgets;
 use crate::widgets::exercise_config_menu::exercise_config_menu;
@@ -45,6 +47,13 @@ impl DepthPerception {
     ///   - correct response = result 1.0
     ///   - incorrect or no response = result 0.0
     fn evaluate_answer(&mut self) {
+        self.evaluation.set_trial_details(
+            serde_json::json!({
+                "circles": self.anaglyph.circles,
+                "target_index": self.anaglyph.target_index,
+            }),
+            self.anaglyph.arrow_position,
+        );
         if self.anaglyph.arrow_position == self.anaglyph.target_index {
             self.evaluation.add_result(1.0)
         } else {
@@ -70,7 +79,7 @@ impl DepthPerception {
             // press enter to give answer
             if i.key_pressed(Key::Enter) {
                 self.evaluate_answer();
-                self.anaglyph.next();
+                self.anaglyph.next(self.evaluation.rng());
             };
         });
     }
@@ -117,7 +126,16 @@ impl DepthPerception {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) =
+            self.evaluation
+                .take_record(self.name(), &self.anaglyph.config.name, score)
+        {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -125,6 +143,8 @@ impl DepthPerception {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -159,6 +179,44 @@ impl Exercise for DepthPerception {
         vec![ExerciseType::Visual]
     }
 
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        let exercise = appdata
+            .excconfig
+            .as_ref()
+            .and_then(|c| find_config(&c.depth_perception, config))
+            .ok_or_else(|| format!("{} has no config named {}.", self.name(), config))?;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.anaglyph.color = appdata.anaglyph_color();
+        self.session = ExerciseStage::Response;
+        self.anaglyph.config = exercise.clone();
+        self.evaluation.start();
+        self.anaglyph.next(self.evaluation.rng());
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.session == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
         let menu_window = egui::Window::new(self.name())
             .anchor(
@@ -181,7 +239,7 @@ impl Exercise for DepthPerception {
                 egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
             }
             ExerciseStage::Finished => {
-                menu_window.show(ctx, |ui| self.finished_screen(ui));
+                menu_window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             _ => {
                 menu_window.show(ctx, |ui| self.ui(ui, appdata, tts));
@@ -198,8 +256,18 @@ impl Exercise for DepthPerception {
                 &mut self.anaglyph.color,
                 &mut self.calibrating,
             );
+            // Store the calibration in the active profile when done.
+            if !self.calibrating {
+                let _ = appdata.profile_tx.send(ProfileChange::AnaglyphColor {
+                    display: appdata.display.clone(),
+                    color: self.anaglyph.color.clone(),
+                });
+            }
             return;
         }
+
+        // Use the calibration of the active profile.
+        self.anaglyph.color = appdata.anaglyph_color();
         ui.label(self.help());
         ui.separator();
 
@@ -211,19 +279,29 @@ impl Exercise for DepthPerception {
             [30, 120],
             [30, 120],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
+        let preferred = appdata.profiles.active().config(self.name());
         let mut func = |config: &DepthPerceptionConfig| {
+            // Remember the chosen config for the active profile.
+            let _ = appdata.profile_tx.send(ProfileChange::Config {
+                exercise: self.name().to_owned(),
+                config: config.name.to_owned(),
+            });
             self.session = ExerciseStage::Response;
             self.anaglyph.config = config.clone();
             self.evaluation.start();
-            self.anaglyph.next();
+            self.anaglyph.next(self.evaluation.rng());
         };
 
         // Display exercise configs
         if let Some(config) = &appdata.excconfig {
-            if let Some(config) =
-                exercise_config_menu::<DepthPerceptionConfig>(ui, &config.depth_perception, 2)
-            {
+            if let Some(config) = exercise_config_menu::<DepthPerceptionConfig>(
+                ui,
+                &config.depth_perception,
+                2,
+                preferred,
+            ) {
                 func(config)
             };
         }
@@ -232,6 +310,7 @@ impl Exercise for DepthPerception {
         ui.add_space(20.);
 
         if ui.button("Calibrate").clicked() {
+            self.anaglyph.color.reset_calibration_values();
             self.calibrating = true
         }
     }
@@ -44,10 +44,7 @@ impl Default for Anaglyph {
 
 impl Anaglyph {
     /// Convert the config values to usable parameters
-    pub fn next(&mut self) {
-        // We need an RNG for much of the following.
-        let mut rng = rand::thread_rng();
-
+    pub fn next<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         // Randomize which circle is the target.
         self.target_index = rng.gen_range(0..self.circles);
 
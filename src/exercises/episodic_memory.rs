@@ -1,8 +1,9 @@
 use crate::shared::asset_loader::sentences::Sentences;
-use crate::shared::{asset_loader, AppData};
-use crate::widgets::{loading_bar_vertical, loading_screen, menu_button};
+use crate::shared::{asset_loader, AppData, ProfileChange};
+use crate::widgets::{self, loading_bar_vertical, loading_screen, menu_button};
 use crate::wm::{Exercise, ExerciseType};
 use egui::{vec2, Align, Color32, RichText, TextEdit, Vec2};
+use rand::prelude::*;
 use tts::{self, Tts};
 
 /// Sequences
@@ -10,6 +11,10 @@ pub struct EpisodicMemory {
     prompts: Sentences,
     session: bool,
     answer: String,
+    /// The questions are shuffled with a seeded rng, so a session can be replayed.
+    replay_seed: Option<u64>,
+    seed: u64,
+    rng: StdRng,
 }
 
 impl Default for EpisodicMemory {
@@ -18,6 +23,9 @@ impl Default for EpisodicMemory {
             prompts: Sentences::default(),
             session: false,
             answer: String::new(),
+            replay_seed: None,
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
         }
     }
 }
@@ -67,7 +75,7 @@ impl EpisodicMemory {
                         // Found contents: store in self and shuffle
                         Ok(file) => {
                             self.prompts.contents = Some(file);
-                            self.prompts.shuffle_contents()
+                            self.prompts.shuffle_contents(&mut self.rng)
                         }
                         // Can't load from disk: create a promise to load from web
                         Err(_) => {
@@ -99,7 +107,7 @@ impl EpisodicMemory {
                         };
 
                     // Finally, shuffle the downloaded/default contents
-                    self.prompts.shuffle_contents()
+                    self.prompts.shuffle_contents(&mut self.rng)
                 }
             }
         }
@@ -153,14 +161,29 @@ impl Exercise for EpisodicMemory {
     fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
         ui.label(self.help());
         ui.separator();
+        widgets::evaluation::seed_widgets(ui, &mut self.replay_seed);
 
+        // Highlight the language preferred by the active profile.
+        let preferred = appdata.profiles.active().language.as_deref();
         if let Some(config) = &appdata.config {
             for file in &config.episodic_memory_files {
-                if menu_button(ui, None, None, file.language.as_str(), "").clicked() {
+                let override_color = match preferred == Some(file.language.as_str()) {
+                    true => Some(ui.visuals().selection.bg_fill),
+                    false => None,
+                };
+                if menu_button(ui, None, override_color, file.language.as_str(), "").clicked() {
+                    // Remember the chosen language for the active profile.
+                    let _ = appdata
+                        .profile_tx
+                        .send(ProfileChange::Language(file.language.to_owned()));
                     self.prompts.selected_file = Some(file.to_owned());
                     // If the selected value changes set the contents to none.
                     // This triggers the contents guarantee and fetches the appropriate file.
                     self.prompts.contents = None;
+                    self.seed = self
+                        .replay_seed
+                        .unwrap_or_else(|| rand::random::<u32>() as u64);
+                    self.rng = StdRng::seed_from_u64(self.seed);
                     self.session = true;
                 };
             }
@@ -182,6 +205,7 @@ impl Exercise for EpisodicMemory {
             if ui.button("Close").clicked() {
                 self.session = false
             };
+            widgets::evaluation::seed_label(ui, self.seed);
         });
 
         ui.vertical_centered(|ui| {
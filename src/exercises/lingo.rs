@@ -0,0 +1,631 @@
+use crate::shared::asset_loader::sentences::{SentenceFile, Sentences};
+use crate::shared::{asset_loader, stats, AppData, Evaluation, ProfileChange, Timer};
+use crate::widgets::{self, loading_screen, menu_button};
+use crate::wm::{Exercise, ExerciseType};
+use chrono::Duration;
+use egui::{
+    vec2, Align, Align2, Color32, FontId, Key, Painter, Rect, Rounding, Sense, TextEdit, Vec2,
+};
+use rand::prelude::*;
+use tts::{self, Tts};
+
+use super::{count_from_config, ExerciseStage};
+
+/// The number of guesses for each word.
+const MAX_GUESSES: usize = 5;
+
+const LINGO_BLUE: Color32 = Color32::from_rgb(30, 80, 160);
+const LINGO_RED: Color32 = Color32::from_rgb(200, 40, 40);
+const LINGO_YELLOW: Color32 = Color32::from_rgb(230, 190, 40);
+
+/// How a letter of a guess compares to the word.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LetterScore {
+    Correct, // Right letter in the right place
+    Present, // The letter is in the word, but in another place
+    Absent,  // The letter isn't in the word (any more times)
+}
+
+/// Score each letter of a guess. A letter that occurs once in the word is only
+/// marked once, with letters in the right place going first.
+fn score_guess(word: &str, guess: &str) -> Vec<LetterScore> {
+    let guess: Vec<char> = guess.chars().collect();
+    let mut remaining: Vec<Option<char>> = word.chars().map(Some).collect();
+    let mut scores = vec![LetterScore::Absent; guess.len()];
+
+    for (i, letter) in guess.iter().enumerate() {
+        if remaining.get(i) == Some(&Some(*letter)) {
+            scores[i] = LetterScore::Correct;
+            remaining[i] = None;
+        }
+    }
+    for (i, letter) in guess.iter().enumerate() {
+        if scores[i] == LetterScore::Correct {
+            continue;
+        }
+        if let Some(pos) = remaining.iter().position(|c| *c == Some(*letter)) {
+            scores[i] = LetterScore::Present;
+            remaining[pos] = None;
+        }
+    }
+    scores
+}
+
+/// All words of the given length, in upper case.
+fn words_of_length(lines: &[String], length: usize) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| line.trim().to_uppercase())
+        .filter(|word| word.chars().count() == length && word.chars().all(|c| c.is_alphabetic()))
+        .collect()
+}
+
+fn default_words() -> Vec<String> {
+    debug!("Getting Lingo words: falling back to default.");
+    let words = vec![
+        "apple", "bread", "chair", "dream", "earth", "glass", "heart", "house", "light", "music",
+        "night", "plant", "river", "table", "water", "animal", "bridge", "castle", "flower",
+        "garden", "island", "market", "orange", "planet", "school", "silver", "winter",
+    ];
+    words.into_iter().map(String::from).collect()
+}
+
+/// A word and the guesses made for it.
+#[derive(Clone, Default)]
+struct Round {
+    word: String,
+    guesses: Vec<String>,
+}
+
+impl Round {
+    fn is_solved(&self) -> bool {
+        self.guesses.last() == Some(&self.word)
+    }
+}
+
+/// Paint the Lingo grid for a round within the given rect. `typing` is shown in the
+/// first empty row. Until something is typed, that row shows the first letter of the word.
+fn paint_grid(painter: &Painter, rect: Rect, round: &Round, typing: Option<&str>) {
+    let columns = round.word.chars().count().max(1);
+    let cell = (rect.width() / columns as f32).min(rect.height() / MAX_GUESSES as f32);
+    let grid = Rect::from_center_size(
+        rect.center(),
+        vec2(cell * columns as f32, cell * MAX_GUESSES as f32),
+    );
+    let font = FontId::monospace(cell * 0.6);
+
+    for row in 0..MAX_GUESSES {
+        // Determine the letters and their scores for this row
+        let letters: Vec<(Option<char>, LetterScore, Color32)> = match round.guesses.get(row) {
+            Some(guess) => guess
+                .chars()
+                .zip(score_guess(&round.word, guess))
+                .map(|(c, score)| (Some(c), score, Color32::WHITE))
+                .collect(),
+            None if row == round.guesses.len() && typing.is_some() => {
+                match typing.unwrap_or_default() {
+                    "" => round
+                        .word
+                        .chars()
+                        .take(1)
+                        .map(|c| (Some(c), LetterScore::Absent, Color32::LIGHT_GRAY))
+                        .collect(),
+                    typed => typed
+                        .chars()
+                        .map(|c| (Some(c), LetterScore::Absent, Color32::WHITE))
+                        .collect(),
+                }
+            }
+            None => vec![],
+        };
+
+        for column in 0..columns {
+            let center = grid.min + vec2((column as f32 + 0.5) * cell, (row as f32 + 0.5) * cell);
+            let square = Rect::from_center_size(center, Vec2::splat(cell * 0.92));
+            let (letter, score, color) =
+                letters
+                    .get(column)
+                    .copied()
+                    .unwrap_or((None, LetterScore::Absent, Color32::WHITE));
+
+            let fill = match score {
+                LetterScore::Correct => LINGO_RED,
+                _ => LINGO_BLUE,
+            };
+            painter.rect_filled(square, Rounding::same(cell * 0.05), fill);
+            if score == LetterScore::Present {
+                painter.circle_filled(center, cell * 0.44, LINGO_YELLOW);
+            }
+            if let Some(letter) = letter {
+                painter.text(center, Align2::CENTER_CENTER, letter, font.clone(), color);
+            }
+        }
+    }
+}
+
+/// Lingo: guess a word in five tries. After each guess, the letters in the right
+/// place turn red and the letters that are in the word, but in another place, get
+/// a yellow circle.
+pub struct Lingo {
+    stage: ExerciseStage,
+    word_length: usize,
+    word_lists: Sentences,
+    words: Vec<String>, // The words of the selected length
+    round: Round,
+    typed: String,
+    rejection: Option<String>, // Why the last guess wasn't accepted
+    rounds: Vec<Round>,        // All finished rounds, for the review
+    result_timer: Timer,
+    evaluation: Evaluation<(usize, bool)>,
+}
+
+impl Default for Lingo {
+    fn default() -> Self {
+        Self {
+            stage: ExerciseStage::None,
+            word_length: 5,
+            word_lists: Sentences::default(),
+            words: vec![],
+            round: Round::default(),
+            typed: String::new(),
+            rejection: None,
+            rounds: vec![],
+            result_timer: Timer::new(),
+            evaluation: Evaluation::new(Duration::try_seconds(300).unwrap_or_default(), 10),
+        }
+    }
+}
+
+impl Lingo {
+    /// Returns a boolean to indicate if the word list is loaded.
+    /// If not, works to retrieve it from disk, web or the defaults, in that order.
+    fn contents_guarantee(&mut self, appdata: &AppData) -> bool {
+        if !self.words.is_empty() {
+            return true;
+        };
+
+        // If we don't have contents, we may have a promise for a web download
+        match &self.word_lists.promise {
+            // No we don't have a promise
+            None => {
+                let Some(file) = &self.word_lists.selected_file else {
+                    return false;
+                };
+                let Some(config) = &appdata.config else {
+                    return false;
+                };
+                let diskpath =
+                    format!("{}{}{}", config.disk_root, config.lingo_path, file.filename);
+                // Try to load contents of selected file from disk
+                match asset_loader::sentences::get_sentences_disk(diskpath) {
+                    Ok(lines) => self.word_lists.contents = Some(lines),
+                    // Can't load from disk: create a promise to load from web
+                    Err(_) => {
+                        let webpath =
+                            format!("{}{}{}", config.web_root, config.lingo_path, file.filename);
+                        self.word_lists.promise =
+                            Some(asset_loader::sentences::get_sentences_web(webpath));
+                    }
+                };
+            }
+            // Yes, we have a promise
+            Some(promise) => match promise.ready() {
+                Some(Ok(resource)) => {
+                    self.word_lists.contents = resource
+                        .text()
+                        .map(asset_loader::sentences::read_sentences_promise)
+                        .and_then(|lines| lines.ok());
+                }
+                Some(Err(e)) => {
+                    warn!("Failed to download Lingo words: {}", e);
+                    self.word_lists.contents = Some(vec![]);
+                }
+                None => (),
+            },
+        }
+
+        // Pick the words of the right length. Without any, fall back to the defaults.
+        if let Some(lines) = &self.word_lists.contents {
+            self.words = words_of_length(lines, self.word_length);
+            if self.words.is_empty() {
+                self.words = words_of_length(&default_words(), self.word_length);
+            }
+        }
+        !self.words.is_empty()
+    }
+
+    /// Pick a new word to guess.
+    fn next_word(&mut self) {
+        let word = self
+            .words
+            .choose(self.evaluation.rng())
+            .cloned()
+            .unwrap_or_default();
+        self.round = Round {
+            word,
+            guesses: vec![],
+        };
+        self.typed = self.given_letter();
+        self.rejection = None;
+        self.evaluation.set_stimulus_onset();
+        self.stage = ExerciseStage::Response;
+    }
+
+    /// The first letter of the word, which is given.
+    fn given_letter(&self) -> String {
+        self.round.word.chars().take(1).collect()
+    }
+
+    /// Check that a guess starts with the given letter. Any word is allowed:
+    /// the word lists only hold the words to guess, not all words of a language.
+    fn check_guess(&self, guess: &str) -> Result<(), String> {
+        match guess.starts_with(&self.given_letter()) {
+            true => Ok(()),
+            false => Err(format!("The word starts with {}.", self.given_letter())),
+        }
+    }
+
+    /// Submit the typed word as a guess, if it is long enough and starts with the given letter.
+    fn submit_guess(&mut self) {
+        if self.typed.chars().count() != self.word_length {
+            return;
+        }
+        if let Err(e) = self.check_guess(&self.typed) {
+            self.rejection = Some(e);
+            return;
+        }
+        self.rejection = None;
+        let given = self.given_letter();
+        self.round
+            .guesses
+            .push(std::mem::replace(&mut self.typed, given));
+        if self.round.is_solved() || self.round.guesses.len() == MAX_GUESSES {
+            self.finish_word();
+        }
+    }
+
+    /// Store the result for the word and show the solution.
+    fn finish_word(&mut self) {
+        self.evaluation.set_trial_details(
+            serde_json::json!({ "word": self.round.word }),
+            &self.round.guesses,
+        );
+        self.evaluation
+            .add_result((self.round.guesses.len(), self.round.is_solved()));
+        self.rounds.push(self.round.clone());
+        self.result_timer
+            .set(Duration::try_seconds(2).unwrap_or_default());
+        self.stage = ExerciseStage::Result;
+    }
+
+    /// Keeps track of exercise progression
+    fn progressor(&mut self) {
+        if self.stage == ExerciseStage::Result && self.result_timer.is_finished() {
+            // Words are only counted when finished, so we only stop between words.
+            match self.evaluation.is_finished() {
+                true => self.stage = ExerciseStage::Finished,
+                false => self.next_word(),
+            }
+        }
+    }
+
+    /// Pick a file and start loading it.
+    fn select_file(&mut self, file: &SentenceFile) {
+        self.word_lists = Sentences::default();
+        self.words = vec![];
+        self.word_lists.selected_file = Some(file.to_owned());
+        self.stage = ExerciseStage::Challenge;
+    }
+
+    /// Review the evaluation.
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        let config = format!("{} letters", self.word_length);
+        if let Some(record) = self.evaluation.take_record(self.name(), &config, score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
+        widgets::evaluation::post_eval_widgets(
+            ui,
+            self.evaluation.average_score(),
+            self.evaluation.reps_done(),
+            self.evaluation.time_taken_as_string(),
+        );
+
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        // Show how hard the words were: the number of guesses needed for solved words.
+        let guesses: Vec<f32> = self
+            .evaluation
+            .show_results()
+            .iter()
+            .filter(|(_, solved)| *solved)
+            .map(|(guesses, _)| *guesses as f32)
+            .collect();
+        match stats::mean(&guesses) {
+            Some(mean) => ui.label(format!(
+                "Solved {} of {} words, in {:.1} guesses on average.",
+                guesses.len(),
+                self.evaluation.reps_done(),
+                mean
+            )),
+            None => ui.label("No words solved."),
+        };
+
+        widgets::evaluation::trial_review(
+            ui,
+            vec2(self.word_length as f32 * 14., MAX_GUESSES as f32 * 14.),
+            &self.rounds,
+            &self.evaluation.scores(),
+            self.evaluation.show_timings(),
+            |painter, to_screen, round| paint_grid(painter, *to_screen.to(), round, None),
+        );
+
+        // Close
+        if ui.button("Close").clicked() {
+            self.reset();
+        }
+    }
+}
+
+impl Exercise for Lingo {
+    fn name(&self) -> &'static str {
+        "Lingo"
+    }
+
+    fn description(&self) -> &'static str {
+        "Guess the word in five tries."
+    }
+
+    fn help(&self) -> &'static str {
+        "Guess the word by typing a word of the same length and pressing enter. The first letter is given. Letters in the right place turn red. Letters that are in the word, but in another place, get a yellow circle."
+    }
+
+    fn excercise_type(&self) -> Vec<ExerciseType> {
+        vec![ExerciseType::Cognitive]
+    }
+
+    fn reset(&mut self) {
+        *self = Lingo::default();
+    }
+
+    /// Configs are named after the word length, e.g. "5 letters".
+    /// The words are in the language preferred by the active profile.
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        self.word_length = count_from_config(config, 5..=6)?;
+        let files = match &appdata.config {
+            Some(config) => &config.lingo_files,
+            None => return Err(format!("{}: no word lists available.", self.name())),
+        };
+        let preferred = appdata.profiles.active().language.as_deref();
+        let file = files
+            .iter()
+            .find(|f| Some(f.language.as_str()) == preferred)
+            .or(files.first())
+            .ok_or(format!("{}: no word lists available.", self.name()))?;
+        self.select_file(file);
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.result_timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.result_timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
+    fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        let window = egui::Window::new(self.name())
+            .anchor(
+                egui::Align2([Align::Center, Align::TOP]),
+                Vec2::new(0., 100.),
+            )
+            .fixed_size(vec2(350., 300.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false);
+
+        match self.stage {
+            ExerciseStage::None => {
+                window.show(ctx, |ui| self.ui(ui, appdata, tts));
+            }
+            ExerciseStage::Finished => {
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
+            }
+            _ => {
+                self.progressor();
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
+            }
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
+        ui.label(self.help());
+        ui.separator();
+
+        widgets::evaluation::eval_config_widgets(
+            ui,
+            &mut self.evaluation.duration,
+            &mut self.evaluation.repetitions,
+            [60, 600],
+            [5, 30],
+        );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
+        ui.horizontal(|ui| {
+            ui.label("Word length");
+            for length in [5, 6] {
+                ui.selectable_value(&mut self.word_length, length, format!("{} letters", length));
+            }
+        });
+
+        // Highlight the language preferred by the active profile.
+        let preferred = appdata.profiles.active().language.as_deref();
+        if let Some(config) = &appdata.config {
+            for file in &config.lingo_files {
+                let override_color = match preferred == Some(file.language.as_str()) {
+                    true => Some(ui.visuals().selection.bg_fill),
+                    false => None,
+                };
+                if menu_button(ui, None, override_color, file.language.as_str(), "").clicked() {
+                    // Remember the chosen language for the active profile.
+                    let _ = appdata
+                        .profile_tx
+                        .send(ProfileChange::Language(file.language.to_owned()));
+                    self.select_file(file);
+                };
+            }
+        }
+    }
+
+    fn session(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
+        // Load the words before starting
+        if self.stage == ExerciseStage::Challenge {
+            if !self.contents_guarantee(appdata) {
+                loading_screen(ui);
+                if ui.button("Cancel").clicked() {
+                    self.reset();
+                }
+                return;
+            }
+            self.evaluation.start();
+            self.next_word();
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.reset();
+            };
+            ui.label(format!(
+                "Time remaining: {}",
+                self.evaluation.time_remaining_as_string()
+            ));
+            ui.label(format!(
+                "Words remaining: {}",
+                self.evaluation.reps_remaining()
+            ));
+        });
+
+        ui.vertical_centered(|ui| {
+            let size = (ui.available_height() * 0.6).min(ui.available_width() * 0.8);
+            let (response, painter) = ui.allocate_painter(Vec2::splat(size), Sense::hover());
+            let typing = match self.stage {
+                ExerciseStage::Response => Some(self.typed.as_str()),
+                _ => None,
+            };
+            paint_grid(&painter, response.rect, &self.round, typing);
+
+            if self.stage == ExerciseStage::Result {
+                let text = match self.round.is_solved() {
+                    true => String::from("Well done!"),
+                    false => format!("The word was {}.", self.round.word),
+                };
+                ui.painter().text(
+                    response.rect.center_bottom() + vec2(0., 20.),
+                    Align2::CENTER_TOP,
+                    text,
+                    FontId::proportional(25.),
+                    ui.visuals().text_color(),
+                );
+                return;
+            }
+
+            // Type the guess. The text field keeps focus, so typing always goes to the grid.
+            ui.add_space(10.);
+            let input = ui.add(
+                TextEdit::singleline(&mut self.typed)
+                    .char_limit(self.word_length)
+                    .desired_width(150.)
+                    .hint_text("Type your guess"),
+            );
+            self.typed = self
+                .typed
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .flat_map(|c| c.to_uppercase())
+                .collect();
+            if input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                self.submit_guess();
+            }
+            input.request_focus();
+            if let Some(rejection) = &self.rejection {
+                ui.label(rejection);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LetterScore::*;
+
+    #[test]
+    fn scoring() {
+        assert_eq!(score_guess("HEART", "HEART"), vec![Correct; 5]);
+        assert_eq!(
+            score_guess("HEART", "EARTH"),
+            vec![Present, Present, Present, Present, Present]
+        );
+        // A letter is only marked as often as it occurs in the word.
+        assert_eq!(
+            score_guess("APPLE", "PAPER"),
+            vec![Present, Present, Correct, Present, Absent]
+        );
+        assert_eq!(
+            score_guess("TOAST", "TTTTT"),
+            vec![Correct, Absent, Absent, Absent, Correct]
+        );
+    }
+
+    #[test]
+    fn word_lists() {
+        let lines: Vec<String> = ["apple", "banana", "e-mail", "Tiger "]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(words_of_length(&lines, 5), vec!["APPLE", "TIGER"]);
+        assert_eq!(words_of_length(&lines, 6), vec!["BANANA"]);
+    }
+
+    #[test]
+    fn guesses() {
+        let mut lingo = Lingo::default();
+        lingo.round.word = String::from("HEART");
+        assert_eq!(lingo.check_guess("HOUSE"), Ok(()));
+        // Words that aren't in the word list are fine too.
+        assert_eq!(lingo.check_guess("HOVEL"), Ok(()));
+        assert!(lingo.check_guess("EARTH").is_err());
+
+        // Rejected guesses don't use up a try.
+        lingo.typed = String::from("EARTH");
+        lingo.submit_guess();
+        assert!(lingo.round.guesses.is_empty());
+        assert!(lingo.rejection.is_some());
+        lingo.typed = String::from("HOUSE");
+        lingo.submit_guess();
+        assert_eq!(lingo.round.guesses, vec!["HOUSE"]);
+        assert_eq!(lingo.typed, "H");
+    }
+}
@@ -3,6 +3,10 @@ mod cog_numbers;
 mod cog_words;
 mod depth_perception;
 mod episodic_memory;
+mod lingo;
+mod multitasker;
+mod n_back;
+mod scattergories;
 mod seq_numbers;
 mod shared;
 pub mod spatial_drawing;
@@ -12,12 +16,17 @@ mod visual_alignment;
 mod visual_puzzles;
 mod visual_recognition;
 mod visual_saccades;
+mod word_breakdown;
 
 pub use binocular_saccades::BinoSaccades;
 pub use cog_numbers::CogNumbers;
 pub use cog_words::CogWords;
 pub use depth_perception::DepthPerception;
 pub use episodic_memory::EpisodicMemory;
+pub use lingo::Lingo;
+pub use multitasker::MultiTasker;
+pub use n_back::NBack;
+pub use scattergories::Scattergories;
 pub use seq_numbers::NumSeq;
 pub use spatial_drawing::SpatialDrawing;
 pub use spatial_hearing::SpatialHearing;
@@ -26,6 +35,7 @@ pub use visual_alignment::VisualAlignment;
 pub use visual_puzzles::{ContainerSearch, NumberedSquares};
 pub use visual_recognition::VisRecognition;
 pub use visual_saccades::VisSaccades;
+pub use word_breakdown::WordBreakdown;
 
 //  **********
 // Some basic supporting stuff
@@ -46,7 +56,7 @@ enum ExerciseStage {
     Finished,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, serde::Serialize)]
 pub enum Direction {
     Up,
     Down,
@@ -54,6 +64,34 @@ pub enum Direction {
     Right,
 }
 
+/// Read the number from a config name like "6 numbers" and check that it is within range.
+fn count_from_config(
+    config: &str,
+    range: std::ops::RangeInclusive<usize>,
+) -> Result<usize, String> {
+    match config.split_whitespace().next().map(|n| n.parse::<usize>()) {
+        Some(Ok(count)) if range.contains(&count) => Ok(count),
+        _ => Err(format!(
+            "Config {} should start with a number from {} to {}.",
+            config,
+            range.start(),
+            range.end()
+        )),
+    }
+}
+
+/// The items in reverse order.
+fn reversed<T: Clone>(items: &[T]) -> Vec<T> {
+    items.iter().rev().cloned().collect()
+}
+
+/// The items in alphabetical order.
+fn alphabetical<T: Ord + Clone>(items: &[T]) -> Vec<T> {
+    let mut sorted = items.to_vec();
+    sorted.sort();
+    sorted
+}
+
 /// Turn a vector of numbers into a string, separating the numbers by a comma
 pub fn numvec_to_string(seq: &Vec<u32>) -> String {
     let mut result = String::new();
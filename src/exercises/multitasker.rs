@@ -0,0 +1,510 @@
+use crate::shared::asset_loader::sentences::{SentenceFile, Sentences};
+use crate::shared::{asset_loader, stats, AppData, Evaluation, ProfileChange, Timer, TrialScore};
+use crate::widgets::{self, loading_screen, menu_button};
+use crate::wm::{Exercise, ExerciseType};
+use chrono::Duration;
+use egui::{vec2, Align, Color32, Key, RichText, TextEdit, Vec2};
+use rand::prelude::*;
+use serde::Serialize;
+use tts::{self, Tts};
+
+use super::ExerciseStage;
+
+/// The part of the recalled words for each channel.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+struct DualScore {
+    visual: f32,
+    auditory: f32,
+}
+
+impl TrialScore for DualScore {
+    fn score(&self) -> f32 {
+        (self.visual + self.auditory) / 2.
+    }
+}
+
+/// The part of the words that is in the answer. Order and case don't matter.
+fn recall_score(words: &[String], answer: &str) -> f32 {
+    if words.is_empty() {
+        return 0.;
+    }
+    let answered: Vec<String> = answer
+        .split(|c: char| !c.is_alphabetic())
+        .map(|word| word.to_lowercase())
+        .collect();
+    let recalled = words
+        .iter()
+        .filter(|word| answered.contains(&word.to_lowercase()))
+        .count();
+    recalled as f32 / words.len() as f32
+}
+
+/// Multitasker: read words flashing by while hearing other words, then recall both.
+pub struct MultiTasker {
+    stage: ExerciseStage,
+    word_lists: Sentences,
+    words: Vec<String>,
+    words_per_channel: usize,
+    step_secs: i64,
+    read: Vec<String>,  // The words shown on screen this round
+    heard: Vec<String>, // The words spoken this round
+    step: usize,
+    step_timer: Timer,
+    answer_read: String,
+    answer_heard: String,
+    evaluation: Evaluation<DualScore>,
+}
+
+impl Default for MultiTasker {
+    fn default() -> Self {
+        Self {
+            stage: ExerciseStage::None,
+            word_lists: Sentences::default(),
+            words: vec![],
+            words_per_channel: 3,
+            step_secs: 2,
+            read: vec![],
+            heard: vec![],
+            step: 0,
+            step_timer: Timer::new(),
+            answer_read: String::new(),
+            answer_heard: String::new(),
+            evaluation: Evaluation::new(Duration::try_seconds(300).unwrap_or_default(), 8),
+        }
+    }
+}
+
+impl MultiTasker {
+    /// Returns a boolean to indicate if the words are loaded.
+    /// If not, works to retrieve them from disk, web or the defaults, in that order.
+    /// Uses the word lists of Lingo.
+    fn contents_guarantee(&mut self, appdata: &AppData) -> bool {
+        if !self.words.is_empty() {
+            return true;
+        };
+
+        // If we don't have contents, we may have a promise for a web download
+        match &self.word_lists.promise {
+            // No we don't have a promise
+            None => {
+                let Some(file) = &self.word_lists.selected_file else {
+                    return false;
+                };
+                let Some(config) = &appdata.config else {
+                    return false;
+                };
+                let diskpath =
+                    format!("{}{}{}", config.disk_root, config.lingo_path, file.filename);
+                // Try to load contents of selected file from disk
+                match asset_loader::sentences::get_sentences_disk(diskpath) {
+                    Ok(lines) => self.word_lists.contents = Some(lines),
+                    // Can't load from disk: create a promise to load from web
+                    Err(_) => {
+                        let webpath =
+                            format!("{}{}{}", config.web_root, config.lingo_path, file.filename);
+                        self.word_lists.promise =
+                            Some(asset_loader::sentences::get_sentences_web(webpath));
+                    }
+                };
+            }
+            // Yes, we have a promise
+            Some(promise) => match promise.ready() {
+                Some(Ok(resource)) => {
+                    self.word_lists.contents = resource
+                        .text()
+                        .map(asset_loader::sentences::read_sentences_promise)
+                        .and_then(|lines| lines.ok());
+                }
+                Some(Err(e)) => {
+                    warn!("Failed to download Multitasker words: {}", e);
+                    self.word_lists.contents = Some(vec![]);
+                }
+                None => (),
+            },
+        }
+
+        // Without enough words for a round, fall back to the defaults.
+        if let Some(lines) = &self.word_lists.contents {
+            let mut words: Vec<String> = lines
+                .iter()
+                .map(|line| line.trim().to_lowercase())
+                .filter(|word| !word.is_empty() && word.chars().all(|c| c.is_alphabetic()))
+                .collect();
+            // The words must be unique, so each one is only heard or read once.
+            words.sort_unstable();
+            words.dedup();
+            if words.len() < 2 * self.words_per_channel {
+                words = asset_loader::sentences::default_sentences()
+                    .iter()
+                    .flat_map(|s| s.split_whitespace())
+                    .map(|word| {
+                        word.trim_matches(|c: char| !c.is_alphabetic())
+                            .to_lowercase()
+                    })
+                    .filter(|word| word.chars().count() > 3)
+                    .collect();
+                words.sort_unstable();
+                words.dedup();
+            }
+            self.words = words;
+        }
+        !self.words.is_empty()
+    }
+
+    /// Pick the words to read and to hear, all different, and start presenting them.
+    fn start_round(&mut self) {
+        let count = self.words_per_channel;
+        let mut picked: Vec<String> = self
+            .words
+            .choose_multiple(self.evaluation.rng(), 2 * count)
+            .cloned()
+            .collect();
+        self.heard = picked.split_off(picked.len().min(count));
+        self.read = picked;
+        self.step = 0;
+        self.answer_read.clear();
+        self.answer_heard.clear();
+        // Give the user a moment to get ready.
+        self.step_timer
+            .set(Duration::try_seconds(1).unwrap_or_default());
+        self.stage = ExerciseStage::Challenge;
+    }
+
+    /// Show and say the next pair of words. After the last pair, ask for the answers.
+    fn next_step(&mut self, spk: &mut tts::Tts) {
+        if self.step >= self.read.len() {
+            self.evaluation.set_stimulus_onset();
+            self.stage = ExerciseStage::Response;
+            return;
+        }
+        if let Some(word) = self.heard.get(self.step) {
+            match spk.speak(word, true) {
+                Ok(_) => debug!("TTS: Word spoken."),
+                Err(e) => warn!("TTS error: {:?}", e),
+            };
+        }
+        self.step += 1;
+        self.step_timer
+            .set(Duration::try_seconds(self.step_secs).unwrap_or_default());
+    }
+
+    /// Score the recall of both channels.
+    fn check_answers(&mut self) {
+        let score = DualScore {
+            visual: recall_score(&self.read, &self.answer_read),
+            auditory: recall_score(&self.heard, &self.answer_heard),
+        };
+        self.evaluation.set_trial_details(
+            serde_json::json!({ "read": self.read, "heard": self.heard }),
+            serde_json::json!({ "read": self.answer_read, "heard": self.answer_heard }),
+        );
+        self.evaluation.add_result(score);
+        self.stage = ExerciseStage::Result;
+    }
+
+    /// The mean accuracy of each channel over all rounds.
+    fn channel_accuracy(&self) -> (f32, f32) {
+        let results = self.evaluation.show_results();
+        let visual: Vec<f32> = results.iter().map(|r| r.visual).collect();
+        let auditory: Vec<f32> = results.iter().map(|r| r.auditory).collect();
+        (
+            stats::mean(&visual).unwrap_or_default(),
+            stats::mean(&auditory).unwrap_or_default(),
+        )
+    }
+
+    /// Pick a file and start loading it.
+    fn select_file(&mut self, file: &SentenceFile) {
+        if self.word_lists.selected_file.as_ref() != Some(file) {
+            self.word_lists = Sentences::default();
+            self.words = vec![];
+            self.word_lists.selected_file = Some(file.to_owned());
+        }
+        // Rounds start once the words are loaded.
+        self.read = vec![];
+        self.stage = ExerciseStage::Challenge;
+    }
+
+    /// List the words of a channel, marking which were recalled.
+    fn recalled_words(ui: &mut egui::Ui, words: &[String], answer: &str) {
+        ui.horizontal_wrapped(|ui| {
+            for word in words {
+                let color = match recall_score(&[word.to_owned()], answer) >= 1. {
+                    true => Color32::GREEN,
+                    false => Color32::RED,
+                };
+                ui.label(RichText::new(word).size(25.).color(color));
+            }
+        });
+    }
+
+    /// Review the evaluation.
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = stats::mean(&self.evaluation.scores()).unwrap_or_default();
+        let config = format!("{} words", self.words_per_channel);
+        if let Some(record) = self.evaluation.take_record(self.name(), &config, score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
+        widgets::evaluation::post_eval_widgets(
+            ui,
+            score,
+            self.evaluation.reps_done(),
+            self.evaluation.time_taken_as_string(),
+        );
+
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        let (visual, auditory) = self.channel_accuracy();
+        ui.label(format!("Words read: {:.0}% recalled", visual * 100.));
+        ui.label(format!("Words heard: {:.0}% recalled", auditory * 100.));
+
+        // Close
+        if ui.button("Close").clicked() {
+            self.reset();
+        }
+    }
+}
+
+impl Exercise for MultiTasker {
+    fn name(&self) -> &'static str {
+        "Multitasker"
+    }
+
+    fn description(&self) -> &'static str {
+        "Read words while hearing other words, then recall both."
+    }
+
+    fn help(&self) -> &'static str {
+        "Words flash by on the screen, while your computers voice says other words at the same time. Afterwards, type the words you read and the words you heard. Both are scored separately."
+    }
+
+    fn excercise_type(&self) -> Vec<ExerciseType> {
+        vec![ExerciseType::Cognitive]
+    }
+
+    fn reset(&mut self) {
+        *self = MultiTasker::default();
+    }
+
+    /// Configs are named after the number of words for each channel, e.g. "3 words".
+    /// The words are in the language preferred by the active profile.
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        self.words_per_channel = super::count_from_config(config, 2..=6)?;
+        let files = match &appdata.config {
+            Some(config) => &config.lingo_files,
+            None => return Err(format!("{}: no word lists available.", self.name())),
+        };
+        let preferred = appdata.profiles.active().language.as_deref();
+        let file = files
+            .iter()
+            .find(|f| Some(f.language.as_str()) == preferred)
+            .or(files.first())
+            .ok_or(format!("{}: no word lists available.", self.name()))?;
+        self.select_file(file);
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.step_timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.step_timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
+    fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        let window = egui::Window::new(self.name())
+            .anchor(
+                egui::Align2([Align::Center, Align::TOP]),
+                Vec2::new(0., 100.),
+            )
+            .fixed_size(vec2(350., 300.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false);
+
+        match self.stage {
+            ExerciseStage::None => {
+                window.show(ctx, |ui| self.ui(ui, appdata, tts));
+            }
+            ExerciseStage::Finished => {
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
+            }
+            _ => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(50));
+                egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
+            }
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
+        ui.label(self.help());
+        ui.separator();
+
+        widgets::evaluation::eval_config_widgets(
+            ui,
+            &mut self.evaluation.duration,
+            &mut self.evaluation.repetitions,
+            [60, 600],
+            [3, 20],
+        );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
+        egui::Grid::new("multitasker_config")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Words to read and to hear");
+                ui.add(egui::Slider::new(&mut self.words_per_channel, 2..=6));
+                ui.end_row();
+
+                ui.label("Seconds per word");
+                ui.add(egui::Slider::new(&mut self.step_secs, 1..=4));
+                ui.end_row();
+            });
+
+        // Highlight the language preferred by the active profile.
+        let preferred = appdata.profiles.active().language.as_deref();
+        if let Some(config) = &appdata.config {
+            for file in &config.lingo_files {
+                let override_color = match preferred == Some(file.language.as_str()) {
+                    true => Some(ui.visuals().selection.bg_fill),
+                    false => None,
+                };
+                if menu_button(ui, None, override_color, file.language.as_str(), "").clicked() {
+                    // Remember the chosen language for the active profile.
+                    let _ = appdata
+                        .profile_tx
+                        .send(ProfileChange::Language(file.language.to_owned()));
+                    self.select_file(file);
+                };
+            }
+        }
+    }
+
+    fn session(&mut self, ui: &mut egui::Ui, appdata: &AppData, tts: &mut Tts) {
+        // Load the words before starting the first round
+        if self.stage == ExerciseStage::Challenge && self.read.is_empty() {
+            if !self.contents_guarantee(appdata) {
+                loading_screen(ui);
+                if ui.button("Cancel").clicked() {
+                    self.reset();
+                }
+                return;
+            }
+            self.evaluation.start();
+            self.start_round();
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.reset();
+            };
+            ui.label(format!(
+                "Time remaining: {}",
+                self.evaluation.time_remaining_as_string()
+            ));
+            ui.label(format!(
+                "Rounds remaining: {}",
+                self.evaluation.reps_remaining()
+            ));
+        });
+
+        let spacer = ui.available_height() / 30.;
+        ui.vertical_centered(|ui| {
+            ui.add_space(spacer * 6.);
+            match self.stage {
+                ExerciseStage::Challenge => {
+                    if self.step_timer.is_finished() {
+                        self.next_step(tts);
+                    }
+                    // Words flash by: each is shown for the first second of its step.
+                    let flashing = self.step_timer.time_passed()
+                        < Duration::try_seconds(1).unwrap_or_default();
+                    match self.read.get(self.step.wrapping_sub(1)) {
+                        Some(word) if flashing => {
+                            ui.heading(RichText::new(word).size(40.));
+                        }
+                        _ if self.step == 0 => {
+                            ui.label(RichText::new("Get ready...").size(25.));
+                        }
+                        _ => (),
+                    }
+                }
+                ExerciseStage::Response => {
+                    ui.label("Type the words you read");
+                    ui.add(TextEdit::singleline(&mut self.answer_read).desired_width(300.));
+                    ui.add_space(spacer);
+                    ui.label("Type the words you heard");
+                    ui.add(TextEdit::singleline(&mut self.answer_heard).desired_width(300.));
+                    ui.add_space(spacer);
+                    if ui
+                        .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Check"))
+                        .clicked()
+                    {
+                        self.check_answers();
+                    };
+                }
+                ExerciseStage::Result => {
+                    ui.label("Words you read");
+                    Self::recalled_words(ui, &self.read, &self.answer_read);
+                    ui.add_space(spacer);
+                    ui.label("Words you heard");
+                    Self::recalled_words(ui, &self.heard, &self.answer_heard);
+                    ui.add_space(spacer);
+                    if ui
+                        .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Next"))
+                        .clicked()
+                        || ui.input(|i| i.key_pressed(Key::Space))
+                    {
+                        match self.evaluation.is_finished() {
+                            true => self.stage = ExerciseStage::Finished,
+                            false => self.start_round(),
+                        }
+                    };
+                    ui.add_space(spacer);
+                    ui.label("Press space for the next round.");
+                }
+                _ => (),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recall() {
+        let words: Vec<String> = ["tiger", "apple", "river"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(recall_score(&words, "River, tiger"), 2. / 3.);
+        assert_eq!(recall_score(&words, "apple tiger river house"), 1.);
+        assert_eq!(recall_score(&words, ""), 0.);
+        assert_eq!(recall_score(&[], "apple"), 0.);
+    }
+}
@@ -0,0 +1,725 @@
+use crate::shared::{stats, AppData, Evaluation, Timer};
+use crate::widgets::{self, menu_button};
+use crate::wm::{Exercise, ExerciseType};
+use chrono::Duration;
+use egui::{emath::RectTransform, vec2, Align, Color32, Key, Pos2, Rect, RichText, Rounding, Vec2};
+use rand::prelude::*;
+use serde::Serialize;
+use tts::{self, Tts};
+
+use super::shared::grid::Grid;
+use super::ExerciseStage;
+
+/// The size of the grid, as `Grid` uses it: positions lie between the edges, so a
+/// grid of size 4 has 3 by 3 positions.
+const GRID_SIZE: usize = 4;
+const POSITIONS: usize = (GRID_SIZE - 1) * (GRID_SIZE - 1);
+/// Letters that are easy to tell apart when spoken.
+const LETTERS: [&str; 8] = ["C", "H", "K", "L", "Q", "R", "S", "T"];
+/// Scored trials per block. Every block has n extra trials to start with.
+const BLOCK_TRIALS: usize = 20;
+/// The chance that a stimulus matches the one n trials back.
+const MATCH_RATE: f64 = 0.3;
+const MAX_N: usize = 9;
+/// N goes up after a block where every channel reaches this accuracy...
+const UP_ACCURACY: f32 = 0.8;
+/// ...and down after a block where a channel falls below this accuracy.
+const DOWN_ACCURACY: f32 = 0.5;
+
+const POSITION: usize = 0;
+const SOUND: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Visual,
+    Auditory,
+    Dual,
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Visual => write!(f, "visual"),
+            Mode::Auditory => write!(f, "auditory"),
+            Mode::Dual => write!(f, "dual"),
+        }
+    }
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Visual, Mode::Auditory, Mode::Dual];
+
+    /// Which channels are used: the position and the sound.
+    fn channels(&self) -> [bool; 2] {
+        match self {
+            Mode::Visual => [true, false],
+            Mode::Auditory => [false, true],
+            Mode::Dual => [true, true],
+        }
+    }
+}
+
+/// Read a config like "2-back, dual". Without a mode, both channels are used.
+fn parse_config(config: &str) -> Result<(usize, Mode), String> {
+    let (n, mode) = match config.split_once(", ") {
+        Some((n, mode)) => (
+            n,
+            *Mode::ALL
+                .iter()
+                .find(|m| m.to_string() == mode)
+                .ok_or(format!("Config {} has an unknown mode.", config))?,
+        ),
+        None => (config, Mode::Dual),
+    };
+    match n.strip_suffix("-back").map(|n| n.parse::<usize>()) {
+        Some(Ok(n)) if (1..=MAX_N).contains(&n) => Ok((n, mode)),
+        _ => Err(format!(
+            "Config {} should start with 1-back up to {}-back.",
+            config, MAX_N
+        )),
+    }
+}
+
+/// Pick `length` stimuli out of `options`. About `MATCH_RATE` of the stimuli
+/// that have one n trials back match it, the others never do.
+fn gen_stream(rng: &mut StdRng, n: usize, length: usize, options: usize) -> Vec<usize> {
+    let mut stream: Vec<usize> = vec![];
+    for i in 0..length {
+        let stimulus = match i >= n && rng.gen_bool(MATCH_RATE) {
+            true => stream[i - n],
+            false => loop {
+                let stimulus = rng.gen_range(0..options);
+                if i < n || stimulus != stream[i - n] {
+                    break stimulus;
+                }
+            },
+        };
+        stream.push(stimulus);
+    }
+    stream
+}
+
+/// The responses to the trials of one channel, in signal detection terms.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+struct Counts {
+    hits: usize,
+    misses: usize,
+    false_alarms: usize,
+    correct_rejections: usize,
+}
+
+impl Counts {
+    /// Count a response. Returns true if it was right.
+    fn add(&mut self, target: bool, pressed: bool) -> bool {
+        match (target, pressed) {
+            (true, true) => self.hits += 1,
+            (true, false) => self.misses += 1,
+            (false, true) => self.false_alarms += 1,
+            (false, false) => self.correct_rejections += 1,
+        }
+        target == pressed
+    }
+
+    /// Hits out of all targets and false alarms. Correct rejections are left out,
+    /// since most trials aren't targets.
+    fn accuracy(&self) -> f32 {
+        let total = self.hits + self.misses + self.false_alarms;
+        match total {
+            0 => 1.,
+            _ => self.hits as f32 / total as f32,
+        }
+    }
+
+    /// The sensitivity d′: how well targets are told apart from other trials.
+    /// Rates are corrected (log-linear), so perfect responses don't give infinity.
+    fn d_prime(&self) -> Option<f32> {
+        let targets = self.hits + self.misses;
+        let lures = self.false_alarms + self.correct_rejections;
+        let hit_rate = (self.hits as f32 + 0.5) / (targets as f32 + 1.);
+        let false_alarm_rate = (self.false_alarms as f32 + 0.5) / (lures as f32 + 1.);
+        Some(stats::probit(hit_rate)? - stats::probit(false_alarm_rate)?)
+    }
+}
+
+/// N for the next block, based on the worst channel of the last block.
+fn adapt(n: usize, counts: &[Counts]) -> usize {
+    let accuracy = counts.iter().map(|c| c.accuracy()).fold(1., f32::min);
+    if accuracy >= UP_ACCURACY {
+        (n + 1).min(MAX_N)
+    } else if accuracy < DOWN_ACCURACY {
+        n.saturating_sub(1).max(1)
+    } else {
+        n
+    }
+}
+
+/// Show positions on a grid and/or speak letters, and press a key whenever the
+/// position or letter is the same as n trials back.
+pub struct NBack {
+    n: usize,
+    start_n: usize,
+    mode: Mode,
+    stage: ExerciseStage,
+    grid: Grid,
+    /// The positions and letters of the current block.
+    streams: [Vec<usize>; 2],
+    trial: usize,
+    /// Was a match pressed in the current trial?
+    pressed: [bool; 2],
+    /// Responses of the last block and of the whole session.
+    block_counts: [Counts; 2],
+    counts: [Counts; 2],
+    blocks: usize,
+    stimulus_ms: i64,
+    stimulus_timer: Timer,
+    trial_ms: i64,
+    trial_timer: Timer,
+    block_break_ms: i64,
+    block_timer: Timer,
+    evaluation: Evaluation<(usize, bool)>,
+}
+
+impl Default for NBack {
+    fn default() -> Self {
+        Self {
+            n: 2,
+            start_n: 2,
+            mode: Mode::Dual,
+            stage: ExerciseStage::None,
+            grid: Grid::new(),
+            streams: [vec![], vec![]],
+            trial: 0,
+            pressed: [false; 2],
+            block_counts: [Counts::default(); 2],
+            counts: [Counts::default(); 2],
+            blocks: 0,
+            stimulus_ms: 500,
+            stimulus_timer: Timer::new(),
+            trial_ms: 2500,
+            trial_timer: Timer::new(),
+            block_break_ms: 3000,
+            block_timer: Timer::new(),
+            evaluation: Evaluation::new(Duration::try_seconds(300).unwrap_or_default(), 100),
+        }
+    }
+}
+
+impl NBack {
+    /// Start the session with a short break, so the first block can be announced.
+    fn start(&mut self, n: usize) {
+        self.n = n;
+        self.start_n = n;
+        self.evaluation.start();
+        self.start_break();
+    }
+
+    fn start_break(&mut self) {
+        self.stage = ExerciseStage::Result;
+        self.block_timer
+            .set(Duration::try_milliseconds(self.block_break_ms).unwrap_or_default());
+    }
+
+    fn start_block(&mut self, tts: &mut Tts) {
+        let length = self.n + BLOCK_TRIALS;
+        let rng = self.evaluation.rng();
+        let positions = gen_stream(rng, self.n, length, POSITIONS);
+        let letters = gen_stream(rng, self.n, length, LETTERS.len());
+        self.streams = [positions, letters];
+        self.block_counts = [Counts::default(); 2];
+        self.blocks += 1;
+        self.trial = 0;
+        self.start_trial(tts);
+    }
+
+    fn start_trial(&mut self, tts: &mut Tts) {
+        self.pressed = [false; 2];
+        self.stage = ExerciseStage::Challenge;
+        self.stimulus_timer
+            .set(Duration::try_milliseconds(self.stimulus_ms).unwrap_or_default());
+        self.trial_timer
+            .set(Duration::try_milliseconds(self.trial_ms).unwrap_or_default());
+        self.evaluation.set_stimulus_onset();
+
+        if self.mode.channels()[SOUND] {
+            let letter = LETTERS[self.streams[SOUND][self.trial]];
+            match tts.speak(letter, true) {
+                Ok(_) => debug!("TTS: Letter spoken."),
+                Err(e) => warn!("TTS error: {:?}", e),
+            };
+        }
+    }
+
+    /// Score the trial, once it can be compared with the one n trials back.
+    fn end_trial(&mut self) {
+        if self.trial < self.n {
+            return;
+        }
+        let mut correct = true;
+        for (channel, used) in self.mode.channels().into_iter().enumerate() {
+            if !used {
+                continue;
+            }
+            let stream = &self.streams[channel];
+            let target = stream[self.trial] == stream[self.trial - self.n];
+            correct &= self.block_counts[channel].add(target, self.pressed[channel]);
+            self.counts[channel].add(target, self.pressed[channel]);
+        }
+        self.evaluation.set_trial_details(
+            serde_json::json!({
+                "n": self.n,
+                "position": self.streams[POSITION][self.trial],
+                "letter": LETTERS[self.streams[SOUND][self.trial]],
+            }),
+            self.pressed,
+        );
+        // Trials without a press have no reaction time.
+        match self.pressed.contains(&true) {
+            true => self.evaluation.add_result((self.n, correct)),
+            false => self.evaluation.add_missed_result((self.n, correct)),
+        }
+    }
+
+    /// Adapt n to the block and take a break before the next block.
+    fn end_block(&mut self) {
+        let counts: Vec<Counts> = self.used_counts(&self.block_counts);
+        self.n = adapt(self.n, &counts);
+        self.start_break();
+    }
+
+    /// The counts of the channels that are used.
+    fn used_counts(&self, counts: &[Counts; 2]) -> Vec<Counts> {
+        self.mode
+            .channels()
+            .into_iter()
+            .zip(counts)
+            .filter(|(used, _)| *used)
+            .map(|(_, counts)| *counts)
+            .collect()
+    }
+
+    /// Keeps track of exercise progression
+    fn progressor(&mut self, tts: &mut Tts) {
+        // end exercise when evaluation is finished.
+        if self.evaluation.is_finished() {
+            self.stage = ExerciseStage::Finished;
+            return;
+        };
+
+        match self.stage {
+            // Showing the stimulus
+            ExerciseStage::Challenge if self.stimulus_timer.is_finished() => {
+                self.stage = ExerciseStage::Response;
+            }
+            // Waiting for the rest of the trial
+            ExerciseStage::Response if self.trial_timer.is_finished() => {
+                self.end_trial();
+                self.trial += 1;
+                match self.trial < self.streams[POSITION].len() {
+                    true => self.start_trial(tts),
+                    false => self.end_block(),
+                }
+            }
+            // A break between blocks
+            ExerciseStage::Result if self.block_timer.is_finished() => {
+                self.start_block(tts);
+            }
+            _ => (),
+        };
+    }
+
+    fn read_keypress(&mut self, ctx: &egui::Context) {
+        if !matches!(
+            self.stage,
+            ExerciseStage::Challenge | ExerciseStage::Response
+        ) {
+            return;
+        }
+        let channels = self.mode.channels();
+        if channels[POSITION] && ctx.input(|i| i.key_pressed(Key::A)) {
+            self.press(POSITION);
+        }
+        if channels[SOUND] && ctx.input(|i| i.key_pressed(Key::L)) {
+            self.press(SOUND);
+        }
+    }
+
+    /// A match was pressed. The reaction time is taken from the first press in a trial.
+    fn press(&mut self, channel: usize) {
+        self.pressed[channel] = true;
+        self.evaluation.set_response_time();
+    }
+
+    /// Draw the grid with the current position lit up.
+    fn draw_session(&mut self, ui: &mut egui::Ui) {
+        let size = ui.available_width().min(ui.available_height() * 0.8);
+        let (_, rect) = ui.allocate_space(vec2(ui.available_width(), size));
+        let to_screen = RectTransform::from_to(
+            Rect::from_x_y_ranges(0.0..=1.0, 0.0..=1.0),
+            Rect::from_center_size(rect.center(), vec2(size, size)),
+        );
+
+        if !self.mode.channels()[POSITION] {
+            return;
+        }
+        let shapes = self
+            .grid
+            .draw_shapes(GRID_SIZE, &to_screen, 1., Color32::from_gray(40), true);
+        ui.painter().extend(shapes);
+
+        if self.stage == ExerciseStage::Challenge {
+            let positions: Vec<Pos2> = self
+                .grid
+                .get_all_coords(GRID_SIZE)
+                .into_iter()
+                .flatten()
+                .collect();
+            if let Some(pos) = positions.get(self.streams[POSITION][self.trial]) {
+                // The same size as the grid shapes, see `Grid::draw_shapes`.
+                let side = 0.9 / GRID_SIZE as f32 * to_screen.scale().min_elem();
+                let square = Rect::from_center_size(to_screen * *pos, vec2(side, side));
+                ui.painter()
+                    .rect_filled(square, Rounding::same(side * 0.15), Color32::LIGHT_BLUE);
+            }
+        }
+    }
+
+    /// Which keys to press, lit up once pressed.
+    fn key_labels(&self, ui: &mut egui::Ui) {
+        let channels = self.mode.channels();
+        ui.horizontal(|ui| {
+            for (channel, label) in [(POSITION, "A: position match"), (SOUND, "L: letter match")] {
+                if !channels[channel] {
+                    continue;
+                }
+                let text = RichText::new(label).size(20.);
+                match self.pressed[channel] {
+                    true => ui.label(text.color(ui.visuals().selection.bg_fill)),
+                    false => ui.label(text),
+                };
+                ui.add_space(40.);
+            }
+        });
+    }
+
+    /// Review the evaluation.
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        let config = format!("{}-back, {}", self.start_n, self.mode);
+        if let Some(record) = self.evaluation.take_record(self.name(), &config, score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
+        widgets::evaluation::post_eval_widgets(
+            ui,
+            self.evaluation.average_score(),
+            self.evaluation.reps_done(),
+            self.evaluation.time_taken_as_string(),
+        );
+
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        ui.label(format!(
+            "Highest level: {}-back",
+            self.evaluation.max_difficulty()
+        ));
+        ui.add_space(10.);
+
+        egui::Grid::new("n_back_counts")
+            .striped(true)
+            .num_columns(6)
+            .show(ui, |ui| {
+                ui.strong("");
+                ui.strong("Hits");
+                ui.strong("Misses");
+                ui.strong("False alarms");
+                ui.strong("Correct rejections");
+                ui.strong("d′");
+                ui.end_row();
+                for (channel, label) in [(POSITION, "Position"), (SOUND, "Letter")] {
+                    if !self.mode.channels()[channel] {
+                        continue;
+                    }
+                    let counts = self.counts[channel];
+                    ui.label(label);
+                    ui.label(counts.hits.to_string());
+                    ui.label(counts.misses.to_string());
+                    ui.label(counts.false_alarms.to_string());
+                    ui.label(counts.correct_rejections.to_string());
+                    match counts.d_prime() {
+                        Some(d_prime) => ui.label(format!("{:.2}", d_prime)),
+                        None => ui.label("-"),
+                    };
+                    ui.end_row();
+                }
+            });
+        ui.add_space(10.);
+
+        // Close
+        if ui.button("Close").clicked() {
+            self.reset();
+        }
+    }
+}
+
+impl Exercise for NBack {
+    fn name(&self) -> &'static str {
+        "N-back"
+    }
+
+    fn description(&self) -> &'static str {
+        "Spot positions and letters that came up n steps back."
+    }
+
+    fn help(&self) -> &'static str {
+        "Every few seconds a square lights up on the grid and a letter is spoken. Press A when the position is the same as n steps back, and L when the letter is. In visual or auditory mode, only the positions or the letters are used.
+
+N goes up when you do well on a block and down when you don't."
+    }
+
+    fn excercise_type(&self) -> Vec<ExerciseType> {
+        vec![
+            ExerciseType::Cognitive,
+            ExerciseType::Visual,
+            ExerciseType::Auditory,
+        ]
+    }
+
+    fn reset(&mut self) {
+        *self = Default::default();
+    }
+
+    /// Configs are named after n and the mode, e.g. "2-back, dual".
+    fn start_with(
+        &mut self,
+        _: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        let (n, mode) = parse_config(config)?;
+        self.mode = mode;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.start(n);
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.stimulus_timer.pause();
+        self.trial_timer.pause();
+        self.block_timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.stimulus_timer.resume();
+        self.trial_timer.resume();
+        self.block_timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
+    fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        // Define menu window
+        let window = egui::Window::new(self.name())
+            .anchor(
+                egui::Align2([Align::Center, Align::TOP]),
+                Vec2::new(0., 100.),
+            )
+            .fixed_size(vec2(500., 300.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false);
+
+        // If we aren't showing the menu or the finished screen, we're in a session.
+        match self.stage {
+            ExerciseStage::None => {
+                window.show(ctx, |ui| self.ui(ui, appdata, tts));
+            }
+            ExerciseStage::Finished => {
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
+            }
+            _ => {
+                self.read_keypress(ctx);
+                ctx.request_repaint_after(std::time::Duration::from_millis(50));
+                egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
+            }
+        };
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _: &AppData, _: &mut Tts) {
+        ui.label(self.help());
+        ui.separator();
+
+        // Show evaluation config
+        widgets::evaluation::eval_config_widgets(
+            ui,
+            &mut self.evaluation.duration,
+            &mut self.evaluation.repetitions,
+            [60, 900],
+            [20, 200],
+        );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
+        // Pick the channels
+        ui.horizontal(|ui| {
+            for mode in Mode::ALL {
+                ui.selectable_value(&mut self.mode, mode, format!("{} n-back", mode));
+            }
+        });
+
+        // Pick the n to start with
+        ui.columns(2, |col| {
+            for n in 1..=4 {
+                if menu_button(&mut col[(n - 1) / 2], None, None, &format!("{n}-back"), "")
+                    .clicked()
+                {
+                    self.start(n);
+                };
+            }
+        });
+    }
+
+    fn session(&mut self, ui: &mut egui::Ui, _: &AppData, tts: &mut Tts) {
+        // Always check progression
+        self.progressor(tts);
+
+        // session menu bar
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                *self = Default::default();
+            };
+            ui.label(format!(
+                "Time remaining: {}",
+                self.evaluation.time_remaining_as_string()
+            ));
+            ui.label(format!(
+                "Reps remaining: {}",
+                self.evaluation.reps_remaining()
+            ));
+            ui.label(format!("Level: {}-back", self.n));
+        });
+
+        if self.stage == ExerciseStage::Result {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.);
+                if self.blocks > 0 {
+                    let counts = self.used_counts(&self.block_counts);
+                    let accuracy = counts.iter().map(|c| c.accuracy()).fold(1., f32::min);
+                    ui.label(format!("Last block: {:.0}%", accuracy * 100.));
+                }
+                ui.heading(RichText::new(format!("{}-back", self.n)).size(40.));
+                ui.label("Get ready.");
+            });
+            return;
+        }
+
+        egui::Frame::dark_canvas(ui.style()).show(ui, |ui| self.draw_session(ui));
+        ui.add_space(10.);
+        ui.vertical_centered(|ui| self.key_labels(ui));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::ManualClock;
+    use std::sync::Arc;
+
+    #[test]
+    fn configs() {
+        assert_eq!(parse_config("2-back, dual"), Ok((2, Mode::Dual)));
+        assert_eq!(parse_config("3-back, auditory"), Ok((3, Mode::Auditory)));
+        assert_eq!(parse_config("1-back"), Ok((1, Mode::Dual)));
+        assert!(parse_config("0-back, visual").is_err());
+        assert!(parse_config("2-back, smell").is_err());
+        assert!(parse_config("2 back").is_err());
+    }
+
+    #[test]
+    fn streams_match_n_back() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let stream = gen_stream(&mut rng, 2, 1000, 8);
+        assert_eq!(stream.len(), 1000);
+        assert!(stream.iter().all(|s| *s < 8));
+        let matches = (2..stream.len())
+            .filter(|i| stream[*i] == stream[i - 2])
+            .count();
+        assert!((200..400).contains(&matches), "{} matches", matches);
+    }
+
+    #[test]
+    fn reaction_times_from_first_press() {
+        let clock = ManualClock::new();
+        let ms = |ms| Duration::try_milliseconds(ms).unwrap();
+        let mut nback = NBack {
+            n: 1,
+            mode: Mode::Visual,
+            streams: [vec![0, 0, 1], vec![0, 0, 0]],
+            evaluation: Evaluation::with_clock(ms(60_000), 10, Arc::new(clock.clone())),
+            ..Default::default()
+        };
+        nback.evaluation.start();
+
+        // A match, pressed twice: timed from the first press, not the end of the trial.
+        nback.trial = 1;
+        nback.evaluation.set_stimulus_onset();
+        clock.advance(ms(400));
+        nback.press(POSITION);
+        clock.advance(ms(300));
+        nback.press(POSITION);
+        clock.advance(ms(1800));
+        nback.end_trial();
+
+        // No match and no press: no reaction time.
+        nback.trial = 2;
+        nback.pressed = [false; 2];
+        nback.evaluation.set_stimulus_onset();
+        clock.advance(ms(2500));
+        nback.end_trial();
+
+        assert_eq!(nback.evaluation.reaction_times_ms(), vec![400.]);
+        assert_eq!(nback.evaluation.show_timings()[1].response, None);
+        assert_eq!(nback.evaluation.show_results(), &vec![(1, true), (1, true)]);
+    }
+
+    #[test]
+    fn grid_positions() {
+        let coords = Grid::new().get_all_coords(GRID_SIZE);
+        assert_eq!(coords.into_iter().flatten().count(), POSITIONS);
+    }
+
+    #[test]
+    fn signal_detection() {
+        let mut counts = Counts::default();
+        assert!(counts.add(true, true));
+        assert!(!counts.add(true, false));
+        assert!(!counts.add(false, true));
+        assert!(counts.add(false, false));
+        assert_eq!(counts.accuracy(), 1. / 3.);
+        // Hit and false alarm rates are the same, so targets aren't told apart.
+        assert_eq!(counts.d_prime(), Some(0.));
+
+        let good = Counts {
+            hits: 6,
+            misses: 0,
+            false_alarms: 0,
+            correct_rejections: 14,
+        };
+        assert!(good.d_prime().unwrap() > 2.);
+
+        // Up when every channel does well, down when any channel does badly.
+        assert_eq!(adapt(2, &[good]), 3);
+        assert_eq!(adapt(2, &[good, counts]), 1);
+        assert_eq!(adapt(1, &[counts]), 1);
+        assert_eq!(adapt(MAX_N, &[good]), MAX_N);
+    }
+}
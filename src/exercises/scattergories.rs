@@ -0,0 +1,384 @@
+use crate::shared::asset_loader::sentences::{SentenceFile, Sentences};
+use crate::shared::asset_loader::word_categories;
+use crate::shared::{asset_loader, AppData, Evaluation, ProfileChange};
+use crate::widgets::{self, loading_screen, menu_button};
+use crate::wm::{Exercise, ExerciseType};
+use chrono::Duration;
+use egui::{vec2, Align, Color32, RichText, TextEdit, Vec2};
+use rand::prelude::*;
+use tts::{self, Tts};
+
+use super::ExerciseStage;
+
+/// The letters to play with. Letters that hardly start any words are left out.
+const LETTERS: &str = "ABCDEFGHIJKLMNOPRSTW";
+
+/// Scattergories: think of a word for each category, starting with a given letter,
+/// before time runs out.
+pub struct Scattergories {
+    stage: ExerciseStage,
+    word_lists: Sentences,
+    categories: Vec<Vec<String>>, // Lists of categories parsed from the file
+    letter: char,
+    round: Vec<String>,   // The categories to answer in this round
+    answers: Vec<String>, // The answer for each category
+    evaluation: Evaluation<bool>,
+}
+
+impl Default for Scattergories {
+    fn default() -> Self {
+        Self {
+            stage: ExerciseStage::None,
+            word_lists: Sentences::default(),
+            categories: vec![],
+            letter: 'A',
+            round: vec![],
+            answers: vec![],
+            evaluation: Evaluation::new(Duration::try_seconds(120).unwrap_or_default(), 12),
+        }
+    }
+}
+
+/// Does the answer start with the given letter? Ignores case and anything
+/// before the first letter, like spaces or quotes.
+fn starts_with_letter(answer: &str, letter: char) -> bool {
+    match answer.chars().find(|c| c.is_alphabetic()) {
+        Some(first) => first.to_lowercase().eq(letter.to_lowercase()),
+        None => false,
+    }
+}
+
+/// Parse the lines of a word categories file. An empty or unreadable file gets the defaults.
+fn parse_or_default(lines: &[String]) -> Vec<Vec<String>> {
+    let lists = word_categories::parse_word_categories(lines);
+    match lists.is_empty() {
+        true => word_categories::default_word_categories(),
+        false => lists,
+    }
+}
+
+impl Scattergories {
+    /// Returns a boolean to indicate if the category lists are loaded.
+    /// If not, works to retrieve them from disk, web or the defaults, in that order.
+    fn contents_guarantee(&mut self, appdata: &AppData) -> bool {
+        if !self.categories.is_empty() {
+            return true;
+        };
+
+        // If we don't have contents, we may have a promise for a web download
+        match &self.word_lists.promise {
+            // No we don't have a promise
+            None => {
+                let Some(file) = &self.word_lists.selected_file else {
+                    return false;
+                };
+                let Some(config) = &appdata.config else {
+                    return false;
+                };
+                let diskpath = format!(
+                    "{}{}{}",
+                    config.disk_root, config.word_categories_path, file.filename
+                );
+                // Try to load contents of selected file from disk
+                match asset_loader::sentences::get_sentences_disk(diskpath) {
+                    Ok(lines) => self.categories = parse_or_default(&lines),
+                    // Can't load from disk: create a promise to load from web
+                    Err(_) => {
+                        let webpath = format!(
+                            "{}{}{}",
+                            config.web_root, config.word_categories_path, file.filename
+                        );
+                        self.word_lists.promise =
+                            Some(asset_loader::sentences::get_sentences_web(webpath));
+                    }
+                };
+            }
+            // Yes, we have a promise
+            Some(promise) => match promise.ready() {
+                Some(Ok(resource)) => {
+                    let lines = resource
+                        .text()
+                        .map(asset_loader::sentences::read_sentences_promise)
+                        .and_then(|lines| lines.ok())
+                        .unwrap_or_default();
+                    self.categories = parse_or_default(&lines);
+                }
+                Some(Err(e)) => {
+                    warn!("Failed to download word categories: {}", e);
+                    self.categories = word_categories::default_word_categories();
+                }
+                None => (),
+            },
+        }
+
+        !self.categories.is_empty()
+    }
+
+    /// Start a round: pick a letter and a list of categories and start the countdown.
+    /// The number of categories is set by the repetitions of the evaluation.
+    fn start_round(&mut self) {
+        self.evaluation.start();
+        let reps = self.evaluation.repetitions;
+        let rng = self.evaluation.rng();
+        self.letter = LETTERS.chars().choose(rng).unwrap_or('A');
+        self.round = match self.categories.choose(rng) {
+            Some(list) => list.choose_multiple(rng, reps).cloned().collect(),
+            None => vec![],
+        };
+        // A list may have fewer categories than asked for.
+        self.evaluation.set_reps(self.round.len());
+        self.answers = vec![String::new(); self.round.len()];
+        self.evaluation.set_stimulus_onset();
+        self.stage = ExerciseStage::Response;
+    }
+
+    /// Score all answers and finish the round.
+    fn finish_round(&mut self) {
+        for (category, answer) in self.round.iter().zip(&self.answers) {
+            let answer = answer.trim();
+            self.evaluation.set_trial_details(
+                serde_json::json!({ "letter": self.letter, "category": category }),
+                answer,
+            );
+            let correct = starts_with_letter(answer, self.letter);
+            match answer.is_empty() {
+                true => self.evaluation.add_missed_result(correct),
+                false => self.evaluation.add_result(correct),
+            }
+        }
+        self.stage = ExerciseStage::Finished;
+    }
+
+    /// Review the evaluation.
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        let config = self
+            .word_lists
+            .selected_file
+            .as_ref()
+            .map(|file| file.language.to_owned())
+            .unwrap_or_default();
+        if let Some(record) = self.evaluation.take_record(self.name(), &config, score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
+        widgets::evaluation::post_eval_widgets(
+            ui,
+            self.evaluation.average_score(),
+            self.evaluation.reps_done(),
+            self.evaluation.time_taken_as_string(),
+        );
+
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        // Show all answers
+        ui.label(format!("Letter: {}", self.letter));
+        egui::Grid::new("scattergories_answers")
+            .striped(true)
+            .show(ui, |ui| {
+                for (category, answer) in self.round.iter().zip(&self.answers) {
+                    ui.label(category);
+                    ui.label(answer.trim());
+                    match starts_with_letter(answer, self.letter) {
+                        true => ui.colored_label(Color32::GREEN, "\u{2714}"),
+                        false => ui.colored_label(Color32::RED, "\u{2716}"),
+                    };
+                    ui.end_row();
+                }
+            });
+
+        // Close
+        if ui.button("Close").clicked() {
+            self.reset();
+        }
+    }
+
+    /// Pick a file and start loading it.
+    fn select_file(&mut self, file: &SentenceFile) {
+        if self.word_lists.selected_file.as_ref() != Some(file) {
+            self.word_lists = Sentences::default();
+            self.categories = vec![];
+            self.word_lists.selected_file = Some(file.to_owned());
+        }
+        self.stage = ExerciseStage::Challenge;
+    }
+}
+
+impl Exercise for Scattergories {
+    fn name(&self) -> &'static str {
+        "Scattergories"
+    }
+
+    fn description(&self) -> &'static str {
+        "Think of words in categories, all starting with the same letter."
+    }
+
+    fn help(&self) -> &'static str {
+        "You get a letter and a list of categories. For each category, type a word that starts with the letter before time runs out. Press Done when you have an answer for every category."
+    }
+
+    fn excercise_type(&self) -> Vec<ExerciseType> {
+        vec![ExerciseType::Cognitive]
+    }
+
+    fn reset(&mut self) {
+        *self = Scattergories::default();
+    }
+
+    /// Configs are languages, e.g. "English".
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        let file = appdata
+            .config
+            .as_ref()
+            .and_then(|c| {
+                c.word_categories_files
+                    .iter()
+                    .find(|f| f.language == config)
+            })
+            .ok_or(format!(
+                "{}: no word categories in {}.",
+                self.name(),
+                config
+            ))?;
+        self.select_file(file);
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
+    fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        let window = egui::Window::new(self.name())
+            .anchor(
+                egui::Align2([Align::Center, Align::TOP]),
+                Vec2::new(0., 100.),
+            )
+            .fixed_size(vec2(350., 300.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false);
+
+        match self.stage {
+            ExerciseStage::None => {
+                window.show(ctx, |ui| self.ui(ui, appdata, tts));
+            }
+            ExerciseStage::Finished => {
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
+            }
+            _ => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
+            }
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
+        ui.label(self.help());
+        ui.separator();
+
+        widgets::evaluation::eval_config_widgets(
+            ui,
+            &mut self.evaluation.duration,
+            &mut self.evaluation.repetitions,
+            [60, 300],
+            [6, 12],
+        );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
+        // Highlight the language preferred by the active profile.
+        let preferred = appdata.profiles.active().language.as_deref();
+        if let Some(config) = &appdata.config {
+            for file in &config.word_categories_files {
+                let override_color = match preferred == Some(file.language.as_str()) {
+                    true => Some(ui.visuals().selection.bg_fill),
+                    false => None,
+                };
+                if menu_button(ui, None, override_color, file.language.as_str(), "").clicked() {
+                    // Remember the chosen language for the active profile.
+                    let _ = appdata
+                        .profile_tx
+                        .send(ProfileChange::Language(file.language.to_owned()));
+                    self.select_file(file);
+                };
+            }
+        }
+    }
+
+    fn session(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
+        // Load the categories before starting the round
+        if self.stage == ExerciseStage::Challenge {
+            if !self.contents_guarantee(appdata) {
+                loading_screen(ui);
+                if ui.button("Cancel").clicked() {
+                    self.reset();
+                }
+                return;
+            }
+            self.start_round();
+        }
+
+        // Time's up
+        if self.evaluation.is_finished() {
+            self.finish_round();
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.reset();
+            };
+            ui.label(format!(
+                "Time remaining: {}",
+                self.evaluation.time_remaining_as_string()
+            ));
+        });
+
+        let spacer = ui.available_height() / 30.;
+        ui.vertical_centered(|ui| {
+            ui.add_space(spacer);
+            ui.heading(RichText::new(self.letter.to_string()).size(60.));
+            ui.add_space(spacer);
+
+            egui::Grid::new("scattergories_categories")
+                .num_columns(2)
+                .spacing(vec2(20., 10.))
+                .show(ui, |ui| {
+                    for (category, answer) in self.round.iter().zip(self.answers.iter_mut()) {
+                        ui.label(RichText::new(category).size(18.));
+                        ui.add(TextEdit::singleline(answer).desired_width(250.));
+                        ui.end_row();
+                    }
+                });
+
+            ui.add_space(spacer);
+            if ui
+                .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Done"))
+                .clicked()
+            {
+                self.finish_round();
+            }
+        });
+    }
+}
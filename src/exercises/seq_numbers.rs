@@ -1,52 +1,252 @@
 use crate::{
-    shared::AppData,
+    shared::{AppData, Evaluation},
+    widgets,
     wm::{Exercise, ExerciseType},
 };
-use egui::{vec2, Align, Vec2};
+use chrono::Duration;
+use egui::{vec2, Align, Color32, Key, RichText, TextEdit, Vec2};
 use rand::prelude::*;
 use tts::Tts;
 
+use super::{count_from_config, ExerciseStage};
+
+mod generators;
+
+use generators::{generators, Additive, SequenceGenerator};
+
+/// The range of operators and operator variance the session adapts within.
+/// Other kinds of sequences join in once they aren't harder than the additive ones.
+const MAX_OPERATORS: usize = 4;
+const MIN_VARIANCE: i32 = 5;
+const MAX_VARIANCE: i32 = 20;
+const VARIANCE_STEP: i32 = 5;
+
+/// The config name of a session, e.g. "2 operators, variance 10, 1 asked".
+fn config_name(operators: usize, variance: i32, asked: usize) -> String {
+    format!(
+        "{} operators, variance {}, {} asked",
+        operators, variance, asked
+    )
+}
+
+/// Read the operators, the variance and the number of asked numbers from a config
+/// name. The variance and asked numbers may be left out, like in "2 operators".
+fn parse_config(config: &str) -> Result<(usize, i32, usize), String> {
+    let mut parts = config.split(',').map(str::trim);
+    let operators = count_from_config(parts.next().unwrap_or_default(), 1..=MAX_OPERATORS)?;
+    let (mut variance, mut asked) = (MIN_VARIANCE, 1);
+    for part in parts {
+        let invalid = || format!("Config {}: can't read {}.", config, part);
+        match part.split_whitespace().collect::<Vec<_>>()[..] {
+            ["variance", n] => variance = n.parse().map_err(|_| invalid())?,
+            [n, "asked"] => asked = n.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        }
+    }
+    if !(MIN_VARIANCE..=MAX_VARIANCE).contains(&variance) || !(1..=3).contains(&asked) {
+        return Err(format!(
+            "Config {}: the variance should be from {} to {} and 1 to 3 numbers asked.",
+            config, MIN_VARIANCE, MAX_VARIANCE
+        ));
+    }
+    Ok((operators, variance, asked))
+}
+
+/// Read the numbers from a typed answer, separated by commas or spaces.
+fn parse_numbers(answer: &str) -> Vec<i32> {
+    answer
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+fn numbers_to_string(numbers: &[i32]) -> String {
+    numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Number sequences: find the rule behind a sequence and calculate the next numbers.
 pub struct NumSeq {
-    operators: Vec<i32>,
+    stage: ExerciseStage,
     sequence: Vec<i32>,
-    seq_length: u32,
+    rule: String,
+    difficulty: usize,
     operators_num: usize,
     operators_var: i32,
-    seq_show: bool,
+    /// The number of operators and their variance at the start of the session.
+    start_operators: usize,
+    start_variance: i32,
+    /// The number of numbers to fill in at the end of the sequence.
+    asked: usize,
+    typed: String,
+    trials: Vec<(Vec<i32>, Vec<i32>)>, // Each sequence with the typed answer
+    evaluation: Evaluation<(usize, bool)>,
 }
 
-impl NumSeq {
-    fn gen_seq(&mut self) -> () {
-        self.sequence.resize(1, 0);
-        self.operators.resize(0, 0);
-        let mut rng = thread_rng();
-        while self.operators.len() < self.operators_num {
-            let sign = if self.operators.len() % 2 == 0 { 1 } else { -1 };
-            let op = sign * rng.gen_range(1..=self.operators_var);
-            if !self.operators.contains(&op) {
-                self.operators.push(op);
-            }
-        }
-        for _ in 1..self.seq_length {
-            for step in &self.operators {
-                // length starts from 1, index starts from 0.
-                let lastnum = self.sequence[self.sequence.len() - 1];
-                let num = lastnum + step;
-                self.sequence.push(num);
-            }
-        }
-        // for _ in self.operators.iter() {}
-    }
-}
 impl Default for NumSeq {
     fn default() -> Self {
         Self {
-            operators: vec![8, -6, 1],
+            stage: ExerciseStage::None,
             sequence: vec![0],
-            seq_length: 5,
+            rule: String::new(),
+            difficulty: 1,
             operators_num: 2,
             operators_var: 10,
-            seq_show: false,
+            start_operators: 2,
+            start_variance: 10,
+            asked: 1,
+            typed: String::new(),
+            trials: vec![],
+            evaluation: Evaluation::new(Duration::try_seconds(300).unwrap_or_default(), 15),
+        }
+    }
+}
+
+impl NumSeq {
+    /// Start the session.
+    fn start(&mut self) {
+        self.start_operators = self.operators_num;
+        self.start_variance = self.operators_var;
+        self.evaluation.start();
+        self.next_sequence();
+    }
+
+    /// Generate a new sequence, with a generator that is at most as hard as
+    /// the additive sequences at the current operators. Finishes when the session is over.
+    fn next_sequence(&mut self) {
+        if self.evaluation.is_finished() {
+            self.stage = ExerciseStage::Finished;
+            return;
+        }
+        let additive = Additive {
+            operators_num: self.operators_num,
+            operators_var: self.operators_var,
+        };
+        let level = additive.difficulty();
+        let mut candidates: Vec<Box<dyn SequenceGenerator>> = generators()
+            .into_iter()
+            .filter(|generator| generator.difficulty() <= level)
+            .collect();
+        candidates.push(Box::new(additive));
+
+        let rng = self.evaluation.rng();
+        let Some(generator) = candidates.choose(rng) else {
+            return;
+        };
+        let sequence = generator.generate(rng, generator.terms_shown() + self.asked);
+        self.sequence = sequence.numbers;
+        self.rule = sequence.rule;
+        self.difficulty = generator.difficulty();
+        self.typed.clear();
+        self.evaluation.set_stimulus_onset();
+        self.stage = ExerciseStage::Response;
+    }
+
+    /// The numbers shown to the user.
+    fn shown(&self) -> &[i32] {
+        &self.sequence[..self.sequence.len() - self.asked]
+    }
+
+    /// The numbers the user should fill in.
+    fn answer(&self) -> &[i32] {
+        &self.sequence[self.sequence.len() - self.asked..]
+    }
+
+    /// Check the typed answer. A correct answer makes the next sequence harder,
+    /// by taking larger steps or, at the largest steps, adding an operator.
+    /// A mistake makes it easier in the same way.
+    fn check_answer(&mut self) {
+        let typed = parse_numbers(&self.typed);
+        let correct = typed == self.answer();
+        self.evaluation.set_trial_details(
+            serde_json::json!({
+                "sequence": self.shown(),
+                "rule": self.rule,
+                "difficulty": self.difficulty,
+            }),
+            &typed,
+        );
+        self.evaluation.add_result((self.difficulty, correct));
+        self.trials.push((self.sequence.to_owned(), typed));
+
+        match correct {
+            true if self.operators_var < MAX_VARIANCE => {
+                self.operators_var = (self.operators_var + VARIANCE_STEP).min(MAX_VARIANCE)
+            }
+            true if self.operators_num < MAX_OPERATORS => {
+                self.operators_num += 1;
+                self.operators_var = MIN_VARIANCE;
+            }
+            false if self.operators_var > MIN_VARIANCE => {
+                self.operators_var = (self.operators_var - VARIANCE_STEP).max(MIN_VARIANCE)
+            }
+            false if self.operators_num > 1 => {
+                self.operators_num -= 1;
+                self.operators_var = MAX_VARIANCE;
+            }
+            _ => (),
+        }
+        self.stage = ExerciseStage::Result;
+    }
+
+    /// Keeps track of exercise progression. Stops when the time is up while answering.
+    /// After an answer, the result is shown first: the next sequence finishes the session.
+    fn progressor(&mut self) {
+        if self.stage == ExerciseStage::Response && self.evaluation.is_finished() {
+            self.stage = ExerciseStage::Finished;
+        }
+    }
+
+    /// Review the evaluation.
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        let config = config_name(self.start_operators, self.start_variance, self.asked);
+        if let Some(record) = self.evaluation.take_record(self.name(), &config, score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
+        widgets::evaluation::post_eval_widgets(
+            ui,
+            self.evaluation.average_score(),
+            self.evaluation.reps_done(),
+            self.evaluation.time_taken_as_string(),
+        );
+
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        ui.label(format!(
+            "Hardest sequence: difficulty {} of 5",
+            self.evaluation.max_difficulty()
+        ));
+
+        // Show all sequences and answers
+        egui::ScrollArea::vertical()
+            .max_height(300.)
+            .show(ui, |ui| {
+                egui::Grid::new("seq_numbers_answers")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for ((sequence, typed), (_, correct)) in
+                            self.trials.iter().zip(self.evaluation.show_results())
+                        {
+                            ui.label(numbers_to_string(sequence));
+                            ui.label(numbers_to_string(typed));
+                            match correct {
+                                true => ui.colored_label(Color32::GREEN, "\u{2714}"),
+                                false => ui.colored_label(Color32::RED, "\u{2716}"),
+                            };
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        // Close
+        if ui.button("Close").clicked() {
+            self.reset();
         }
     }
 }
@@ -61,7 +261,9 @@ impl Exercise for NumSeq {
     }
 
     fn help(&self) -> &'static str {
-        "Randomly generate a string of numbers according to simple rules. Then try to calculate and call out each number in your mind."
+        "A sequence of numbers is made by taking the same steps over and over, like +8 then -6. Find the steps and type the next numbers of the sequence.
+
+The sequences get harder as long as your answers are right, with larger steps and more of them. After a mistake they get easier."
     }
 
     fn excercise_type(&self) -> Vec<ExerciseType> {
@@ -72,8 +274,40 @@ impl Exercise for NumSeq {
         *self = Default::default();
     }
 
+    /// Configs are named after the number of operators and the variance to start with,
+    /// and the number of asked numbers, e.g. "2 operators, variance 10, 1 asked".
+    fn start_with(
+        &mut self,
+        _: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        (self.operators_num, self.operators_var, self.asked) = parse_config(config)?;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.start();
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
-        egui::Window::new(self.name())
+        let window = egui::Window::new(self.name())
             .anchor(
                 egui::Align2([Align::Center, Align::TOP]),
                 Vec2::new(0., 100.),
@@ -81,54 +315,163 @@ impl Exercise for NumSeq {
             .fixed_size(vec2(350., 300.))
             .resizable(false)
             .movable(false)
-            .collapsible(false)
-            .show(ctx, |ui| self.ui(ui, appdata, tts));
+            .collapsible(false);
+
+        match self.stage {
+            ExerciseStage::None => {
+                window.show(ctx, |ui| self.ui(ui, appdata, tts));
+            }
+            ExerciseStage::Finished => {
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
+            }
+            _ => {
+                self.progressor();
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
+            }
+        }
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, _appdata: &AppData, _: &mut Tts) {
-        ui.vertical(|ui| {
-            ui.horizontal(|ui| {
-                if ui.button("Generate").clicked() {
-                    self.gen_seq();
+        ui.label(self.help());
+        ui.separator();
+
+        widgets::evaluation::eval_config_widgets(
+            ui,
+            &mut self.evaluation.duration,
+            &mut self.evaluation.repetitions,
+            [60, 600],
+            [5, 50],
+        );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
+        egui::Grid::new("seq_numbers_config")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Operators");
+                ui.add(egui::Slider::new(
+                    &mut self.operators_num,
+                    1..=MAX_OPERATORS,
+                ));
+                ui.end_row();
+
+                ui.label("Operator variance");
+                ui.add(egui::Slider::new(
+                    &mut self.operators_var,
+                    MIN_VARIANCE..=MAX_VARIANCE,
+                ));
+                ui.end_row();
+
+                ui.label("Numbers to fill in");
+                ui.add(egui::Slider::new(&mut self.asked, 1..=3));
+                ui.end_row();
+            });
+
+        ui.add_space(10.);
+        if ui.button("Start").clicked() {
+            self.start();
+        }
+    }
+
+    fn session(&mut self, ui: &mut egui::Ui, _: &AppData, _: &mut Tts) {
+        let spacer = ui.available_height() / 30.;
+
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.reset();
+            };
+            ui.label(format!(
+                "Time remaining: {}",
+                self.evaluation.time_remaining_as_string()
+            ));
+            ui.label(format!(
+                "Reps remaining: {}",
+                self.evaluation.reps_remaining()
+            ));
+        });
+
+        ui.vertical_centered(|ui| {
+            ui.add_space(spacer * 4.);
+            let blanks = vec!["?"; self.asked].join(", ");
+            ui.heading(
+                RichText::new(format!("{}, {}", numbers_to_string(self.shown()), blanks)).size(30.),
+            );
+            ui.add_space(spacer * 2.);
+
+            match self.stage {
+                ExerciseStage::Response => {
+                    ui.label(match self.asked {
+                        1 => String::from("Type the next number."),
+                        n => format!("Type the next {} numbers.", n),
+                    });
+                    ui.add_space(spacer);
+                    let input = ui.add(
+                        TextEdit::singleline(&mut self.typed)
+                            .font(egui::TextStyle::Heading)
+                            .desired_width(200.),
+                    );
+                    if input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                        self.check_answer();
+                    }
+                    input.request_focus();
+
+                    ui.add_space(spacer);
+                    if ui
+                        .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Check"))
+                        .clicked()
+                    {
+                        self.check_answer();
+                    };
+                    ui.add_space(spacer);
+                    ui.label("Press return to check your answer.");
                 }
-                if ui.button("Clear").clicked() {
-                    self.sequence.clear();
+                ExerciseStage::Result => {
+                    let (text, color) = match self.evaluation.show_results().last() {
+                        Some((_, true)) => ("Correct!", Color32::GREEN),
+                        _ => ("Not quite.", Color32::RED),
+                    };
+                    ui.label(RichText::new(text).size(25.).color(color));
+                    ui.add_space(spacer);
+                    ui.label(&self.rule);
+                    ui.heading(RichText::new(numbers_to_string(&self.sequence)).size(25.));
+
+                    ui.add_space(spacer);
+                    if ui
+                        .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Next"))
+                        .clicked()
+                        || ui.input(|i| i.key_pressed(Key::Space))
+                    {
+                        self.next_sequence();
+                    };
+                    ui.add_space(spacer);
+                    ui.label("Press space for the next sequence.");
                 }
-                ui.checkbox(&mut self.seq_show, "Show sequence");
-            });
-            egui::Grid::new("my_grid")
-                .num_columns(2)
-                .spacing([40.0, 4.0])
-                .striped(true)
-                .show(ui, |ui| {
-                    ui.label("Operators");
-                    ui.add(egui::Slider::new(&mut self.operators_num, 0..=5));
-                    ui.end_row();
-
-                    ui.label("Operator variance");
-                    ui.add(egui::Slider::new(&mut self.operators_var, 0..=20));
-                    ui.end_row();
-
-                    ui.label("Sequence length");
-                    ui.add(egui::Slider::new(&mut self.seq_length, 0..=25));
-                    ui.end_row();
-
-                    ui.separator();
-                    ui.separator();
-                    ui.end_row();
-
-                    ui.label("Operators");
-                    ui.heading(format!("{:?}", &self.operators));
-                    ui.end_row();
-                });
-            if self.seq_show {
-                ui.separator();
-                ui.heading(format!("{:?}", &self.sequence));
+                _ => (),
             }
         });
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configs() {
+        let config = config_name(3, 15, 2);
+        assert_eq!(config, "3 operators, variance 15, 2 asked");
+        assert_eq!(parse_config(&config), Ok((3, 15, 2)));
+        assert_eq!(parse_config("2 operators"), Ok((2, MIN_VARIANCE, 1)));
+        assert!(parse_config("2 operators, variance 50").is_err());
+        assert!(parse_config("2 operators, 4 asked").is_err());
+        assert!(parse_config("2 operators, hard").is_err());
+    }
 
-    fn session(&mut self, _: &mut egui::Ui, _: &AppData, _: &mut Tts) {
-        todo!()
+    #[test]
+    fn answers() {
+        assert_eq!(parse_numbers("11, 5 -3"), vec![11, 5, -3]);
+        assert_eq!(numbers_to_string(&[0, -6, 12]), "0, -6, 12");
     }
 }
@@ -0,0 +1,338 @@
+//! Generators for number sequences. Every generator makes sequences that follow
+//! one kind of rule and rates how hard that rule is to find. Sequences that
+//! hardly need a rule to continue, like a constant or a repeating sequence, are
+//! rejected and generated again.
+
+use rand::prelude::*;
+
+/// How often a generator may retry when it makes a degenerate sequence.
+const MAX_TRIES: usize = 100;
+
+/// A generated sequence with the rule that made it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence {
+    pub numbers: Vec<i32>,
+    pub rule: String,
+}
+
+pub trait SequenceGenerator {
+    /// How hard it is to find the rule, from 1 (easy) to 5 (hard).
+    fn difficulty(&self) -> usize;
+
+    /// The number of terms to show, so the rule can be found.
+    fn terms_shown(&self) -> usize;
+
+    /// Generate a sequence of the given length. May be degenerate: use
+    /// `generate` to get a sequence that isn't.
+    fn try_generate(&self, rng: &mut StdRng, length: usize) -> Sequence;
+
+    /// Generate a sequence of the given length that isn't degenerate.
+    fn generate(&self, rng: &mut StdRng, length: usize) -> Sequence {
+        let mut sequence = self.try_generate(rng, length);
+        for _ in 1..MAX_TRIES {
+            if !is_degenerate(&sequence.numbers) {
+                break;
+            }
+            sequence = self.try_generate(rng, length);
+        }
+        sequence
+    }
+}
+
+/// A sequence is degenerate when there's nothing to find: it repeats itself,
+/// which includes being constant, or it counts up or down by one.
+pub fn is_degenerate(numbers: &[i32]) -> bool {
+    let repeats = (1..=numbers.len() / 2)
+        .any(|period| numbers.iter().zip(&numbers[period..]).all(|(a, b)| a == b));
+    let counts = numbers
+        .windows(2)
+        .all(|pair| (pair[1] - pair[0]).abs() <= 1);
+    repeats || counts
+}
+
+/// Describe a step, e.g. "+8" or "-6".
+fn step(n: i32) -> String {
+    format!("{:+}", n)
+}
+
+/// Steps that alternately add and subtract up to `operators_var`, taken in turn.
+pub struct Additive {
+    pub operators_num: usize,
+    pub operators_var: i32,
+}
+
+impl Additive {
+    /// Pick the operators. Operators are unique, if the variance allows it.
+    fn operators(&self, rng: &mut StdRng) -> Vec<i32> {
+        let operators_var = self.operators_var.max(1);
+        let mut operators: Vec<i32> = vec![];
+        while operators.len() < self.operators_num.max(1) {
+            let sign = if operators.len().is_multiple_of(2) {
+                1
+            } else {
+                -1
+            };
+            let op = sign * rng.gen_range(1..=operators_var);
+            // Each sign has operators_var different operators to choose from.
+            let same_sign = operators.iter().filter(|o| o.signum() == sign).count();
+            if !operators.contains(&op) || same_sign >= operators_var as usize {
+                operators.push(op);
+            }
+        }
+        operators
+    }
+}
+
+impl SequenceGenerator for Additive {
+    fn difficulty(&self) -> usize {
+        (self.operators_num + (self.operators_var > 10) as usize).clamp(1, 5)
+    }
+
+    fn terms_shown(&self) -> usize {
+        2 * self.operators_num.max(1) + 1
+    }
+
+    fn try_generate(&self, rng: &mut StdRng, length: usize) -> Sequence {
+        let operators = self.operators(rng);
+        let mut numbers = vec![rng.gen_range(1..=20)];
+        for i in 1..length {
+            numbers.push(numbers[i - 1] + operators[(i - 1) % operators.len()]);
+        }
+        let steps: Vec<String> = operators.iter().map(|op| step(*op)).collect();
+        Sequence {
+            numbers,
+            rule: format!("Take the steps {} in turn.", steps.join(", ")),
+        }
+    }
+}
+
+/// Multiply by the same factor every step, optionally followed by adding a number.
+pub struct Multiplicative {
+    pub with_addition: bool,
+}
+
+impl SequenceGenerator for Multiplicative {
+    fn difficulty(&self) -> usize {
+        match self.with_addition {
+            true => 4,
+            false => 2,
+        }
+    }
+
+    fn terms_shown(&self) -> usize {
+        4
+    }
+
+    fn try_generate(&self, rng: &mut StdRng, length: usize) -> Sequence {
+        let factor = rng.gen_range(2..=3);
+        let addition = match self.with_addition {
+            true => *[-3, -2, -1, 1, 2, 3].choose(rng).unwrap_or(&1),
+            false => 0,
+        };
+        let mut numbers: Vec<i32> = vec![rng.gen_range(1..=5)];
+        for i in 1..length {
+            numbers.push(
+                numbers[i - 1]
+                    .saturating_mul(factor)
+                    .saturating_add(addition),
+            );
+        }
+        let rule = match addition {
+            0 => format!("Multiply by {}.", factor),
+            _ => format!("Multiply by {}, then {}.", factor, step(addition)),
+        };
+        Sequence { numbers, rule }
+    }
+}
+
+/// Two sequences taking turns, each with its own step.
+pub struct Interleaved;
+
+impl SequenceGenerator for Interleaved {
+    fn difficulty(&self) -> usize {
+        3
+    }
+
+    fn terms_shown(&self) -> usize {
+        6
+    }
+
+    fn try_generate(&self, rng: &mut StdRng, length: usize) -> Sequence {
+        let starts = [rng.gen_range(1..=20), rng.gen_range(1..=20)];
+        let steps = [rng.gen_range(-9..=9), rng.gen_range(-9..=9)];
+        let numbers = (0..length)
+            .map(|i| starts[i % 2] + (i / 2) as i32 * steps[i % 2])
+            .collect();
+        Sequence {
+            numbers,
+            rule: format!(
+                "Two sequences take turns: one takes steps of {}, the other of {}.",
+                step(steps[0]),
+                step(steps[1])
+            ),
+        }
+    }
+}
+
+/// Every number is the sum of the two numbers before it.
+pub struct Fibonacci;
+
+impl SequenceGenerator for Fibonacci {
+    fn difficulty(&self) -> usize {
+        3
+    }
+
+    fn terms_shown(&self) -> usize {
+        5
+    }
+
+    fn try_generate(&self, rng: &mut StdRng, length: usize) -> Sequence {
+        let mut numbers: Vec<i32> = vec![rng.gen_range(1..=9), rng.gen_range(1..=9)];
+        numbers.truncate(length);
+        for i in 2..length {
+            numbers.push(numbers[i - 1].saturating_add(numbers[i - 2]));
+        }
+        Sequence {
+            numbers,
+            rule: String::from("Add the two numbers before."),
+        }
+    }
+}
+
+/// The squares of consecutive numbers, plus the same number every time.
+pub struct Squares;
+
+impl SequenceGenerator for Squares {
+    fn difficulty(&self) -> usize {
+        4
+    }
+
+    fn terms_shown(&self) -> usize {
+        5
+    }
+
+    fn try_generate(&self, rng: &mut StdRng, length: usize) -> Sequence {
+        let first = rng.gen_range(1..=6);
+        let offset = rng.gen_range(-5..=5);
+        let numbers = (0..length as i32)
+            .map(|i| (first + i).pow(2) + offset)
+            .collect();
+        let rule = match offset {
+            0 => format!("The squares of {}, {} and so on.", first, first + 1),
+            _ => format!(
+                "The squares of {}, {} and so on, {}.",
+                first,
+                first + 1,
+                step(offset)
+            ),
+        };
+        Sequence { numbers, rule }
+    }
+}
+
+/// All generators but the additive one. Sessions make their own additive
+/// generator, because they adapt its operators.
+pub fn generators() -> Vec<Box<dyn SequenceGenerator>> {
+    vec![
+        Box::new(Multiplicative {
+            with_addition: false,
+        }),
+        Box::new(Multiplicative {
+            with_addition: true,
+        }),
+        Box::new(Interleaved),
+        Box::new(Fibonacci),
+        Box::new(Squares),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn all_generators() -> Vec<Box<dyn SequenceGenerator>> {
+        let mut all = generators();
+        for operators_num in 1..=4 {
+            for operators_var in [5, 10, 20] {
+                all.push(Box::new(Additive {
+                    operators_num,
+                    operators_var,
+                }));
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn degenerate_sequences() {
+        assert!(is_degenerate(&[4, 4, 4, 4]));
+        assert!(is_degenerate(&[3, 8, 3, 8, 3]));
+        assert!(is_degenerate(&[1, 2, 3, 4, 5]));
+        assert!(is_degenerate(&[5, 4, 4, 3, 2]));
+        assert!(!is_degenerate(&[1, 3, 5, 7, 9]));
+        assert!(!is_degenerate(&[0, 8, 2, 10, 4, 12]));
+        assert!(!is_degenerate(&[1, 1, 2, 3, 5, 8]));
+
+        // Steps of at most 1 can only make degenerate sequences. Generating
+        // them gives up after a while instead of trying forever.
+        let mut rng = StdRng::seed_from_u64(1);
+        let additive = Additive {
+            operators_num: 2,
+            operators_var: 1,
+        };
+        assert_eq!(additive.generate(&mut rng, 6).numbers.len(), 6);
+    }
+
+    #[test]
+    fn additive_operators() {
+        // Operators alternate between adding and subtracting, and terminate
+        // even when there are fewer different steps than operators.
+        let mut rng = StdRng::seed_from_u64(3);
+        let additive = Additive {
+            operators_num: 4,
+            operators_var: 10,
+        };
+        let operators = additive.operators(&mut rng);
+        assert!(operators[0] > 0 && operators[1] < 0 && operators[2] > 0 && operators[3] < 0);
+        let additive = Additive {
+            operators_num: 4,
+            operators_var: 1,
+        };
+        assert_eq!(additive.operators(&mut rng), vec![1, -1, 1, -1]);
+    }
+
+    proptest! {
+        #[test]
+        fn sequences_have_the_requested_length(seed: u64, extra in 1..4usize) {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for generator in all_generators() {
+                let length = generator.terms_shown() + extra;
+                let sequence = generator.generate(&mut rng, length);
+                prop_assert_eq!(sequence.numbers.len(), length);
+                prop_assert!(!sequence.rule.is_empty());
+            }
+        }
+
+        #[test]
+        fn sequences_are_not_degenerate(seed: u64) {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for generator in all_generators() {
+                let length = generator.terms_shown() + 3;
+                let sequence = generator.generate(&mut rng, length);
+                prop_assert!(!is_degenerate(&sequence.numbers), "{:?}", sequence);
+            }
+        }
+
+        #[test]
+        fn difficulty_is_rated(seed: u64) {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for generator in all_generators() {
+                prop_assert!((1..=5).contains(&generator.difficulty()));
+                let sequence = generator.generate(&mut rng, generator.terms_shown() + 3);
+                // Numbers stay small enough to calculate by heart.
+                prop_assert!(sequence.numbers.iter().all(|n| n.abs() < 100_000));
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use egui::{emath::RectTransform, pos2, Color32, Painter, Pos2, Rect, Stroke};
+
+use crate::{exercises::Direction, widgets};
+
+/// A single trial of an exercise that shows an arrow somewhere on the screen:
+/// where the arrow was, where it pointed and the response of the user, if any.
+/// Kept to review the session trial by trial.
+#[derive(Debug, Clone, Copy)]
+pub struct ArrowTrial {
+    /// Normalized position of the arrow on the screen.
+    pub pos: Pos2,
+    pub answer: Direction,
+    pub response: Option<Direction>,
+}
+
+impl ArrowTrial {
+    /// Paint a trial on a review thumbnail: a miniature screen with a dot at the position
+    /// of the arrow, followed by the arrow itself and the response arrow.
+    pub fn paint(&self, painter: &Painter, to_screen: RectTransform) {
+        let screen = Rect::from_min_max(pos2(0.1, 0.1), pos2(1.5, 0.9));
+        painter.rect_stroke(
+            to_screen.transform_rect(screen),
+            2.,
+            Stroke::new(1., Color32::GRAY),
+        );
+        painter.circle_filled(
+            to_screen * (screen.min + self.pos.to_vec2() * screen.size()),
+            3.,
+            Color32::LIGHT_GREEN,
+        );
+
+        painter.add(widgets::arrow_shape(
+            pos2(1.9, 0.5),
+            30.,
+            &self.answer,
+            to_screen,
+            Color32::LIGHT_GREEN,
+        ));
+        if let Some(response) = &self.response {
+            painter.add(widgets::arrow_shape(
+                pos2(2.4, 0.5),
+                30.,
+                response,
+                to_screen,
+                Color32::KHAKI,
+            ));
+        }
+    }
+}
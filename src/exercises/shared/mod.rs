@@ -1 +1,2 @@
+pub mod arrow_trial;
 pub mod grid;
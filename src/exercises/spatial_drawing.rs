@@ -51,7 +51,6 @@ impl std::fmt::Display for SessionStatus {
     }
 }
 
-#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct SpatialDrawing {
     state: SessionStatus,
     puzzle_grid: Grid,
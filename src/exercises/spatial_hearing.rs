@@ -25,7 +25,6 @@ struct SpaceDimensions {
     z: usize,
 }
 
-#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct SpatialHearing {
     device_url: String,
     status: ExerciseStage,
@@ -126,8 +125,7 @@ impl SpatialHearing {
                 debug!("SpatialHearing: Pre-challenge timer finished.");
 
                 // Pick a random soundsource
-                let mut rng = rand::thread_rng();
-                if let Some(sourcesource) = self.sound_sources.choose(&mut rng) {
+                if let Some(sourcesource) = self.sound_sources.choose(self.evaluation.rng()) {
                     self.answer = Some(sourcesource.to_owned());
                 };
 
@@ -150,6 +148,10 @@ impl SpatialHearing {
                 if self.response.is_some() {
                     debug!("SpatialHearing: User response received.");
                     // Evaluate response and store result
+                    self.evaluation.set_trial_details(
+                        serde_json::json!({ "coords": self.answer.as_ref().map(|a| a.coords) }),
+                        self.response.as_ref().map(|r| r.coords),
+                    );
                     self.evaluation.add_result(self.evaluate_response());
                     // Move to next step
                     self.status = ExerciseStage::Result
@@ -238,7 +240,13 @@ impl SpatialHearing {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) = self.evaluation.take_record(self.name(), "Default", score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -246,6 +254,8 @@ impl SpatialHearing {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -274,6 +284,16 @@ impl Exercise for SpatialHearing {
         ""
     }
 
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.timer.resume();
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut tts::Tts) {
         let menu_window = egui::Window::new(self.name())
             .anchor(
@@ -293,7 +313,7 @@ impl Exercise for SpatialHearing {
             }
             // After an exercise, show the review
             ExerciseStage::Finished => {
-                menu_window.show(ctx, |ui| self.finished_screen(ui));
+                menu_window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             // Any other status means we are in session.
             _ => {
@@ -314,6 +334,7 @@ impl Exercise for SpatialHearing {
             [30, 300],
             [10, 50],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
         // Display spatial setup config on dark background
         ui.set_min_height(300.);
@@ -5,9 +5,9 @@ use tts::Tts;
 
 use crate::exercises::Direction;
 
-use crate::shared::asset_loader::exercise_config::vergence::VergenceConfig;
-use crate::shared::AppData;
+use crate::shared::asset_loader::exercise_config::{find_config, vergence::VergenceConfig};
 use crate::shared::Evaluation;
+use crate::shared::{AppData, ProfileChange};
 use crate::widgets;
 use crate::widgets::evaluation::eval_config_widgets;
 use crate::widgets::exercise_config_menu::exercise_config_menu;
@@ -39,6 +39,7 @@ pub struct Vergence {
     evaluation: Evaluation<bool>,
     session: Session,
     step: isize,
+    config_name: String,
 }
 
 impl Default for Vergence {
@@ -49,6 +50,7 @@ impl Default for Vergence {
             evaluation: Evaluation::new(Duration::try_seconds(60).unwrap_or_default(), 60),
             session: Session::default(),
             step: 0,
+            config_name: String::new(),
         }
     }
 }
@@ -61,6 +63,14 @@ impl Vergence {
     /// the streak of right answers continues to build. When the wrong answer is given,
     /// reset the exercise difficulty.
     fn evaluate_answer(&mut self, a: Direction) {
+        self.evaluation.set_trial_details(
+            serde_json::json!({
+                "focal_position": self.anaglyph.focal_position,
+                "background_offset": self.anaglyph.background_offset,
+            }),
+            a,
+        );
+
         // If the answer is correct, add true to the results vec.
         // If the previous answer was also correct (indicated by the answer threshold),
         // increase the difficulty of the exercise.
@@ -109,7 +119,7 @@ impl Vergence {
             }
         }
         // create arrays for a new anaglyph
-        self.anaglyph.initialize();
+        self.anaglyph.initialize(self.evaluation.rng());
     }
 
     fn read_keypress(&mut self, ctx: &egui::Context) -> Option<Direction> {
@@ -167,7 +177,16 @@ impl Vergence {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) = self
+            .evaluation
+            .take_record(self.name(), &self.config_name, score)
+        {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -175,6 +194,8 @@ impl Vergence {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -206,10 +227,59 @@ impl Exercise for Vergence {
         let tmp_color = self.anaglyph.color.clone();
         *self = Default::default();
         // create arrays for a new anaglyph
-        self.anaglyph.initialize();
+        self.anaglyph.initialize(self.evaluation.rng());
         self.anaglyph.color = tmp_color;
     }
 
+    /// Convergence and divergence configs share names, so a config can be
+    /// prefixed with its kind: "Divergence: Easy (tiny)". Without a prefix
+    /// convergence configs are tried first.
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        let excconfig = appdata
+            .excconfig
+            .as_ref()
+            .ok_or_else(|| String::from("No exercise configs loaded."))?;
+        let config_level = match config.split_once(": ") {
+            Some(("Convergence", name)) => find_config(&excconfig.convergence, name),
+            Some(("Divergence", name)) => find_config(&excconfig.divergence, name),
+            _ => find_config(&excconfig.convergence, config)
+                .or_else(|| find_config(&excconfig.divergence, config)),
+        }
+        .ok_or_else(|| format!("{} has no config named {}.", self.name(), config))?;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.anaglyph.color = appdata.anaglyph_color();
+        self.step = config_level.step;
+        self.config_name = config_level.name.to_owned();
+        self.anaglyph.pixel_size = config_level.pixel_size;
+        self.session.active = true;
+        self.evaluation.start();
+        self.anaglyph.initialize(self.evaluation.rng());
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.session.active && self.evaluation.is_finished()
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
         let menu_window = egui::Window::new("Vergence")
             .anchor(
@@ -227,7 +297,7 @@ impl Exercise for Vergence {
         match self.session.active {
             true => match self.evaluation.is_finished() {
                 true => {
-                    menu_window.show(ctx, |ui| self.finished_screen(ui));
+                    menu_window.show(ctx, |ui| self.finished_screen(ui, appdata));
                 }
                 false => {
                     egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
@@ -251,9 +321,19 @@ impl Exercise for Vergence {
                 &mut self.anaglyph.color,
                 &mut self.calibrating,
             );
+            // Store the calibration in the active profile when done.
+            if !self.calibrating {
+                let _ = appdata.profile_tx.send(ProfileChange::AnaglyphColor {
+                    display: appdata.display.clone(),
+                    color: self.anaglyph.color.clone(),
+                });
+            }
             return;
         }
 
+        // Use the calibration of the active profile.
+        self.anaglyph.color = appdata.anaglyph_color();
+
         ui.label(self.help());
         ui.separator();
 
@@ -265,13 +345,21 @@ impl Exercise for Vergence {
             [30, 120],
             [30, 120],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
+        let preferred = appdata.profiles.active().config(self.name());
         let mut func = |config_level: &VergenceConfig| {
-            self.anaglyph.initialize();
+            // Remember the chosen config for the active profile.
+            let _ = appdata.profile_tx.send(ProfileChange::Config {
+                exercise: self.name().to_owned(),
+                config: config_level.name.to_owned(),
+            });
             self.step = config_level.step;
+            self.config_name = config_level.name.to_owned();
             self.anaglyph.pixel_size = config_level.pixel_size;
             self.session.active = true;
             self.evaluation.start();
+            self.anaglyph.initialize(self.evaluation.rng());
         };
 
         // Display exercise configs
@@ -281,16 +369,22 @@ impl Exercise for Vergence {
                 .drag_to_scroll(true)
                 .show(ui, |ui| {
                     ui.heading("Convergence");
-                    if let Some(config) =
-                        exercise_config_menu::<VergenceConfig>(ui, &excconfig.convergence, 3)
-                    {
+                    if let Some(config) = exercise_config_menu::<VergenceConfig>(
+                        ui,
+                        &excconfig.convergence,
+                        3,
+                        preferred,
+                    ) {
                         func(config)
                     };
 
                     ui.heading("Divergence");
-                    if let Some(config) =
-                        exercise_config_menu::<VergenceConfig>(ui, &excconfig.divergence, 3)
-                    {
+                    if let Some(config) = exercise_config_menu::<VergenceConfig>(
+                        ui,
+                        &excconfig.divergence,
+                        3,
+                        preferred,
+                    ) {
                         func(config)
                     };
                 });
@@ -300,6 +394,7 @@ impl Exercise for Vergence {
         ui.add_space(20.);
 
         if ui.button("Calibrate").clicked() {
+            self.anaglyph.color.reset_calibration_values();
             self.calibrating = true
         }
     }
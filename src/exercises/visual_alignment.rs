@@ -6,8 +6,8 @@ use rand::Rng;
 use tts::Tts;
 
 use crate::shared::AnaglyphColor;
-use crate::shared::AppData;
 use crate::shared::Evaluation;
+use crate::shared::{AppData, ProfileChange};
 use crate::widgets;
 use crate::wm::{Exercise, ExerciseType};
 
@@ -85,6 +85,10 @@ impl VisualAlignment {
         // start the next challenge
         if self.status == ExerciseStage::Response {
             let target_sight_offset = self.evaluate_answer();
+            self.evaluation.set_trial_details(
+                serde_json::json!({ "target": self.target_pos_on_screen }),
+                self.sight_pos_on_screen,
+            );
             self.evaluation.add_result(target_sight_offset);
             self.target_pos_normalized = self.gen_random_pos2();
             self.status = ExerciseStage::Challenge
@@ -163,7 +167,7 @@ impl VisualAlignment {
 
     /// Generate random  directions and push to answer vec.
     fn gen_random_pos2(&mut self) -> Pos2 {
-        let mut rng = rand::thread_rng();
+        let rng = self.evaluation.rng();
         let x: f32 = rng.gen_range(0.2..0.8);
         let y: f32 = rng.gen_range(0.2..0.8);
         pos2(x, y)
@@ -200,7 +204,13 @@ impl VisualAlignment {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) = self.evaluation.take_record(self.name(), "Default", score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -208,21 +218,35 @@ impl VisualAlignment {
             self.evaluation.time_taken_as_string(),
         );
 
-        let x_shift = {
-            let mut x_total = 0.;
-            for result in self.evaluation.show_results() {
-                x_total += result.x;
-            }
-            x_total / self.evaluation.show_results().len() as f32
-        };
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
 
-        let y_shift = {
-            let mut total = 0.;
-            for result in self.evaluation.show_results() {
-                total += result.y;
-            }
-            total / self.evaluation.show_results().len() as f32
+        // How far off the aim was, and how the scores were spread over the session.
+        let percent = |score: Option<f32>| match score {
+            Some(score) => format!("{:.0}%", score * 100.),
+            None => String::from("-"),
         };
+        egui::Grid::new("visual_alignment_stats")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Average distance:");
+                ui.label(format!("{:.1} points", self.evaluation.mean_error()));
+                ui.end_row();
+                ui.label("Median score:");
+                ui.label(percent(self.evaluation.score_median()));
+                ui.end_row();
+                ui.label("Standard deviation:");
+                ui.label(percent(self.evaluation.score_std_dev()));
+                ui.end_row();
+                ui.label("Worst 10% of aims score below:");
+                ui.label(percent(self.evaluation.score_percentile(10.)));
+                ui.end_row();
+                ui.label("Change per aim:");
+                ui.label(percent(self.evaluation.learning_slope()));
+                ui.end_row();
+            });
+
+        let bias = self.evaluation.bias();
+        let (x_shift, y_shift) = (bias.x, bias.y);
 
         ui.horizontal(|ui| {
             ui.label("Average horizontal shift:");
@@ -278,6 +302,34 @@ impl Exercise for VisualAlignment {
         self.colors = tmp_color;
     }
 
+    /// This exercise has a single config, so the config name is ignored.
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        _: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        self.colors = appdata.anaglyph_color();
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.evaluation.start();
+        self.status = ExerciseStage::Challenge;
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.status == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
         let menu_window = egui::Window::new("Vergence")
             .anchor(
@@ -302,7 +354,7 @@ impl Exercise for VisualAlignment {
 
             // Show finished menu
             ExerciseStage::Finished => {
-                menu_window.show(ctx, |ui| self.finished_screen(ui));
+                menu_window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
 
             // All other statuses mean we are in session
@@ -322,13 +374,23 @@ impl Exercise for VisualAlignment {
     }
 
     /// The exercise menu
-    fn ui(&mut self, ui: &mut egui::Ui, _: &AppData, _: &mut Tts) {
+    fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
         // Calibration guard clause
         if self.calibrating {
             widgets::calibrate_anaglyph::calibrate(ui, &mut self.colors, &mut self.calibrating);
+            // Store the calibration in the active profile when done.
+            if !self.calibrating {
+                let _ = appdata.profile_tx.send(ProfileChange::AnaglyphColor {
+                    display: appdata.display.clone(),
+                    color: self.colors.clone(),
+                });
+            }
             return;
         }
 
+        // Use the calibration of the active profile.
+        self.colors = appdata.anaglyph_color();
+
         ui.label(self.help());
         ui.separator();
 
@@ -340,6 +402,8 @@ impl Exercise for VisualAlignment {
             [5, 30],
         );
 
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
         if ui.button("Start").clicked() {
             self.evaluation.start();
             self.status = ExerciseStage::Challenge;
@@ -349,6 +413,7 @@ impl Exercise for VisualAlignment {
         ui.add_space(20.);
 
         if ui.button("Calibrate").clicked() {
+            self.colors.reset_calibration_values();
             self.calibrating = true
         }
     }
@@ -11,7 +11,7 @@ use tts::{self, Tts};
 mod game_logic;
 mod session_ui;
 use crate::exercises::shared::grid::Grid;
-use crate::exercises::ExerciseStage;
+use crate::exercises::{count_from_config, ExerciseStage};
 
 /// The user is shown a number of containers. In one of the containers is a secret.
 /// The secret is found when the container is opened by clicking on it. The container
@@ -23,6 +23,7 @@ use crate::exercises::ExerciseStage;
 pub struct ContainerSearch {
     stage: ExerciseStage,
     num_containers: usize, // basic difficulty setting
+    config_name: String,
     containers: Containers,
     grid: Grid,
     grid_size: usize,
@@ -37,6 +38,7 @@ impl Default for ContainerSearch {
         Self {
             containers: Containers::default(),
             num_containers: 4,
+            config_name: String::new(),
             stage: ExerciseStage::None,
             grid: Grid::new(),
             grid_size: 10,
@@ -50,7 +52,16 @@ impl Default for ContainerSearch {
 
 impl ContainerSearch {
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) = self
+            .evaluation
+            .take_record(self.name(), &self.config_name, score)
+        {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -58,6 +69,8 @@ impl ContainerSearch {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -100,7 +113,43 @@ impl Exercise for ContainerSearch {
         *self = Default::default();
     }
 
-    /// Show the configuration dialog
+    /// Configs are named after the number of items, e.g. "6 containers".
+    fn start_with(
+        &mut self,
+        _: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        self.num_containers = count_from_config(config, 4..=10)?;
+        self.config_name = format!("{} containers", self.num_containers);
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.evaluation.start();
+        self.gen_containers();
+        self.gen_secret();
+        self.stage = ExerciseStage::Response;
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.result_timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.result_timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
         // Define menu window
         let window = egui::Window::new(self.name())
@@ -119,7 +168,7 @@ impl Exercise for ContainerSearch {
                 window.show(ctx, |ui| self.ui(ui, appdata, tts));
             }
             ExerciseStage::Finished => {
-                window.show(ctx, |ui| self.finished_screen(ui));
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             _ => {
                 self.progressor();
@@ -131,6 +180,7 @@ impl Exercise for ContainerSearch {
         if self.stage != ExerciseStage::None {}
     }
 
+    /// Show the configuration dialog
     fn ui(&mut self, ui: &mut egui::Ui, _: &AppData, _: &mut Tts) {
         ui.label(self.help());
         ui.separator();
@@ -143,10 +193,12 @@ impl Exercise for ContainerSearch {
             [30, 600],
             [5, 60],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
         // Draw a menu in two columns
         let mut func = |i| {
             self.num_containers = i;
+            self.config_name = format!("{i} containers");
             self.evaluation.start();
             self.gen_containers();
             self.gen_secret();
@@ -29,7 +29,7 @@ impl ContainerSearch {
     /// Generates a sequence of valid and unique positions on the grid.
     pub(super) fn gen_containers(&mut self) {
         self.containers.clear();
-        let mut rng = thread_rng();
+        let rng = self.evaluation.rng();
         let all_coords: Vec<Pos2> = self
             .grid
             .get_all_coords(self.grid_size)
@@ -49,12 +49,12 @@ impl ContainerSearch {
 
     /// Selects one container as containing the secret
     pub fn gen_secret(&mut self) {
-        let mut rng = rand::thread_rng();
+        let rng = self.evaluation.rng();
         let mut attempts = 0;
         const MAX_ATTEMPTS: usize = 1000;
 
         while attempts < MAX_ATTEMPTS {
-            if let Some(secret) = self.containers.unopened.choose(&mut rng) {
+            if let Some(secret) = self.containers.unopened.choose(rng) {
                 if !self.containers.found_secrets.contains(secret) {
                     self.containers.secret = *secret;
                     return;
@@ -11,9 +11,9 @@ mod session;
 use tts::{self, Tts};
 
 use crate::exercises::shared::grid::Grid;
-use crate::exercises::ExerciseStage;
+use crate::exercises::{count_from_config, ExerciseStage};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct Answers {
     sequence: Vec<Pos2>,
     response: Vec<Pos2>,
@@ -24,6 +24,7 @@ pub struct NumberedSquares {
     seq_length: usize,
     stage: ExerciseStage,
     answers: Answers,
+    trials: Vec<Answers>, // Every sequence and the response to it
     grid: Grid,
     grid_size: usize,
     challenge_ms: i64,
@@ -39,6 +40,7 @@ impl Default for NumberedSquares {
     fn default() -> Self {
         Self {
             answers: Answers::default(),
+            trials: vec![],
             seq_length: 4,
             stage: ExerciseStage::None,
             grid: Grid::new(),
@@ -96,7 +98,12 @@ impl NumberedSquares {
 
     /// Evaluate response, store result, move on to next challenge
     fn next(&mut self) {
+        self.evaluation.set_trial_details(
+            serde_json::json!({ "sequence": self.answers.sequence }),
+            &self.answers.response,
+        );
         self.evaluation.add_result(self.evaluate_response());
+        self.trials.push(self.answers.clone());
         self.gen_sequence();
         self.answers.response.clear();
         self.stage = ExerciseStage::Challenge;
@@ -115,7 +122,7 @@ impl NumberedSquares {
     /// Generates a sequence of valid and unique positions on the grid
     fn gen_sequence(&mut self) {
         self.answers.sequence.clear();
-        let mut rng = thread_rng();
+        let rng = self.evaluation.rng();
         let all_coords: Vec<Pos2> = self
             .grid
             .get_all_coords(self.grid_size)
@@ -135,7 +142,16 @@ impl NumberedSquares {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) =
+            self.evaluation
+                .take_record(self.name(), &format!("{} numbers", self.seq_length), score)
+        {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -143,6 +159,18 @@ impl NumberedSquares {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        let grid_size = self.grid_size;
+        widgets::evaluation::trial_review(
+            ui,
+            vec2(150., 150.),
+            &self.trials,
+            &self.evaluation.scores(),
+            self.evaluation.show_timings(),
+            |painter, to_screen, trial| Self::paint_trial(painter, to_screen, trial, grid_size),
+        );
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -193,7 +221,47 @@ impl Exercise for NumberedSquares {
         *self = Default::default();
     }
 
-    /// Show the configuration dialog
+    /// Configs are named after the number of items, e.g. "6 numbers".
+    fn start_with(
+        &mut self,
+        _: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        self.seq_length = count_from_config(config, 4..=10)?;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.evaluation.start();
+        self.gen_sequence();
+        self.stage = ExerciseStage::Challenge;
+        self.challenge_timer
+            .set(Duration::try_milliseconds(self.challenge_ms).unwrap_or_default());
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.challenge_timer.pause();
+        self.response_timer.pause();
+        self.result_timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.challenge_timer.resume();
+        self.response_timer.resume();
+        self.result_timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
         // Define menu window
         let window = egui::Window::new(self.name())
@@ -212,7 +280,7 @@ impl Exercise for NumberedSquares {
                 window.show(ctx, |ui| self.ui(ui, appdata, tts));
             }
             ExerciseStage::Finished => {
-                window.show(ctx, |ui| self.finished_screen(ui));
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             _ => {
                 self.progressor();
@@ -224,6 +292,7 @@ impl Exercise for NumberedSquares {
         if self.stage != ExerciseStage::None {}
     }
 
+    /// Show the configuration dialog
     fn ui(&mut self, ui: &mut egui::Ui, _: &AppData, _: &mut Tts) {
         ui.label(self.help());
         ui.separator();
@@ -236,6 +305,7 @@ impl Exercise for NumberedSquares {
             [30, 600],
             [5, 60],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
         // Draw a menu in two columns
         let mut func = |i| {
@@ -1,11 +1,11 @@
 use egui::{
     emath, epaint::RectShape, pos2, vec2, Align2, Color32, FontId, Pos2, Rect, Response, Rounding,
-    Sense,
+    Sense, Stroke,
 };
 
 use crate::exercises::ExerciseStage;
 
-use super::NumberedSquares;
+use super::{Answers, NumberedSquares};
 
 impl NumberedSquares {
     pub(super) fn draw_debug(&mut self, ui: &mut egui::Ui) -> Response {
@@ -101,6 +101,50 @@ impl NumberedSquares {
         response
     }
 
+    /// Paint a trial on a review thumbnail. The squares of the sequence are numbered.
+    /// Each click in the response gets a green outline if it was in the right place
+    /// and a red one if not. Wrong squares are numbered in the order they were clicked.
+    pub(super) fn paint_trial(
+        painter: &egui::Painter,
+        to_screen: emath::RectTransform,
+        trial: &Answers,
+        grid_size: usize,
+    ) {
+        let abs_size = (1.0 / grid_size as f32) * 0.9 * to_screen.scale().min_elem();
+        for (i, pos) in trial.sequence.iter().enumerate() {
+            let rect = Rect::from_center_size(to_screen * *pos, vec2(abs_size, abs_size));
+            painter.rect_filled(rect, Rounding::same(abs_size * 0.15), Color32::DARK_GREEN);
+            painter.text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                format!("{}", i + 1),
+                FontId::monospace(abs_size * 0.8),
+                Color32::WHITE,
+            );
+        }
+        for (i, pos) in trial.response.iter().enumerate() {
+            let rect = Rect::from_center_size(to_screen * *pos, vec2(abs_size, abs_size));
+            let color = match trial.sequence.get(i) == Some(pos) {
+                true => Color32::GREEN,
+                false => Color32::RED,
+            };
+            painter.rect_stroke(
+                rect,
+                Rounding::same(abs_size * 0.15),
+                Stroke::new(2., color),
+            );
+            if !trial.sequence.contains(pos) {
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    format!("{}", i + 1),
+                    FontId::monospace(abs_size * 0.8),
+                    color,
+                );
+            }
+        }
+    }
+
     /// Show squares on the grid, show challenge, show result
     pub(super) fn draw_session(&mut self, ui: &mut egui::Ui) -> Response {
         // Setup
@@ -1,5 +1,7 @@
 use crate::exercises::Direction;
-use crate::shared::asset_loader::exercise_config::visual_recognition::VisRecognitionConfig;
+use crate::shared::asset_loader::exercise_config::{
+    find_config, visual_recognition::VisRecognitionConfig,
+};
 use crate::widgets;
 use crate::widgets::evaluation::eval_config_widgets;
 use crate::widgets::exercise_config_menu::exercise_config_menu;
@@ -7,7 +9,7 @@ use crate::widgets::exercise_config_menu::exercise_config_menu;
 use crate::wm::ExerciseType;
 use crate::{
     wm::Exercise,
-    {shared::AppData, shared::Evaluation, shared::Timer},
+    {shared::AppData, shared::Evaluation, shared::ProfileChange, shared::Timer},
 };
 use chrono::Duration;
 use egui::{emath, pos2, vec2, Align, Color32, Frame, Key, Rect, Vec2};
@@ -20,13 +22,13 @@ use super::ExerciseStage;
 /// Draws a number of arrows in the middle of the screen. The arrows remain visible
 /// for a short period of time. Then player presses the arrow keys to indicate the
 /// sequence of arrows they have seen.
-#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct VisRecognition {
     session_status: ExerciseStage,
     exercise_params: VisRecognitionConfig,
     answer: Vec<Direction>,   // The correct answer: a sequence of directions
     timer: Timer,             // Keeps track of timeouts
     response: Vec<Direction>, // The response given by the player
+    trials: Vec<(Vec<Direction>, Vec<Direction>)>, // Every answer and the response to it
     evaluation: Evaluation<f32>,
 }
 
@@ -38,6 +40,7 @@ impl Default for VisRecognition {
             answer: vec![],
             timer: Timer::new(),
             response: vec![],
+            trials: vec![],
             evaluation: Evaluation::new(Duration::try_seconds(60).unwrap_or_default(), 60),
         }
     }
@@ -109,13 +112,30 @@ impl VisRecognition {
                 Direction::Up,
                 Direction::Down,
             ]
-            .choose(&mut rand::thread_rng())
+            .choose(self.evaluation.rng())
             {
                 self.answer.push(*direction);
             }
         }
     }
 
+    /// Paint a trial on a review thumbnail: the answer on top, the response below.
+    fn paint_trial(
+        painter: &egui::Painter,
+        to_screen: emath::RectTransform,
+        (answer, response): &(Vec<Direction>, Vec<Direction>),
+    ) {
+        for (row, directions, color) in [
+            (0.3, answer, Color32::LIGHT_GREEN),
+            (0.7, response, Color32::KHAKI),
+        ] {
+            for (i, direction) in directions.iter().enumerate() {
+                let pos = pos2(0.25 + i as f32 * 0.35, row);
+                painter.add(widgets::arrow_shape(pos, 15., direction, to_screen, color));
+            }
+        }
+    }
+
     /// Read arrow keys and register response.
     fn read_keypress(&mut self, ctx: &egui::Context) {
         let mut eval = |response: Direction| {
@@ -178,6 +198,8 @@ impl VisRecognition {
                     self.add_arrows();
                 }
                 if self.timer.is_finished() {
+                    // The response is timed from the moment the arrows disappear.
+                    self.evaluation.set_stimulus_onset();
                     self.session_status = ExerciseStage::Response;
                 }
             }
@@ -189,7 +211,13 @@ impl VisRecognition {
                 if self.response.len() == self.answer.len() {
                     // store evaluation result
                     let score = self.evaluate_response();
+                    self.evaluation.set_trial_details(
+                        serde_json::json!({ "sequence": self.answer }),
+                        &self.response,
+                    );
                     self.evaluation.add_result(score);
+                    self.trials
+                        .push((self.answer.clone(), self.response.clone()));
                     // Set a two second timer to display result
                     self.timer.set(Duration::try_seconds(2).unwrap_or_default());
                     // Progress to result
@@ -236,7 +264,16 @@ impl VisRecognition {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) =
+            self.evaluation
+                .take_record(self.name(), &self.exercise_params.name, score)
+        {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -244,6 +281,26 @@ impl VisRecognition {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        // Make room for the longest sequence of arrows
+        let longest = self.trials.iter().map(|t| t.0.len()).max().unwrap_or(0);
+        widgets::evaluation::trial_review(
+            ui,
+            vec2((0.5 + longest.saturating_sub(1) as f32 * 0.35) * 60., 60.),
+            &self.trials,
+            &self.evaluation.scores(),
+            self.evaluation.show_timings(),
+            Self::paint_trial,
+        );
+
+        widgets::evaluation::reaction_time_widgets(
+            ui,
+            self.evaluation.reaction_time_mean(),
+            self.evaluation.reaction_time_median(),
+            self.evaluation.reaction_time_variance(),
+        );
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -272,6 +329,44 @@ impl Exercise for VisRecognition {
         "This exercise shows a number of arrows. After the arrows disappear, quickly enter the corresponding arrows on the keyboard."
     }
 
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        let exercise = appdata
+            .excconfig
+            .as_ref()
+            .and_then(|c| find_config(&c.visual_recognition, config))
+            .ok_or_else(|| format!("{} has no config named {}.", self.name(), config))?;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.exercise_params = exercise.to_owned();
+        self.session_status = ExerciseStage::Challenge;
+        self.evaluation.start();
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.session_status == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut tts::Tts) {
         let default_window = egui::Window::new(self.name())
             .anchor(
@@ -290,7 +385,7 @@ impl Exercise for VisRecognition {
             }
             // After an evaluation show the review
             ExerciseStage::Finished => {
-                default_window.show(ctx, |ui| self.finished_screen(ui));
+                default_window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             // Any other status means we are in session.
             _ => {
@@ -315,9 +410,16 @@ impl Exercise for VisRecognition {
             [30, 120],
             [30, 120],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
         // Anonymous function that uses the exercise config
+        let preferred = appdata.profiles.active().config(self.name());
         let mut func = |exercise: &VisRecognitionConfig| {
+            // Remember the chosen config for the active profile.
+            let _ = appdata.profile_tx.send(ProfileChange::Config {
+                exercise: self.name().to_owned(),
+                config: exercise.name.to_owned(),
+            });
             self.exercise_params = exercise.to_owned();
             self.session_status = ExerciseStage::Challenge;
             self.evaluation.start();
@@ -325,9 +427,12 @@ impl Exercise for VisRecognition {
 
         // Display exercise configs
         if let Some(config) = &appdata.excconfig {
-            if let Some(config) =
-                exercise_config_menu::<VisRecognitionConfig>(ui, &config.visual_recognition, 2)
-            {
+            if let Some(config) = exercise_config_menu::<VisRecognitionConfig>(
+                ui,
+                &config.visual_recognition,
+                2,
+                preferred,
+            ) {
                 func(config)
             };
         }
@@ -1,6 +1,9 @@
+use crate::exercises::shared::arrow_trial::ArrowTrial;
 use crate::exercises::Direction;
-use crate::shared::asset_loader::exercise_config::visual_saccades::VisSaccadesConfig;
-use crate::shared::AppData;
+use crate::shared::asset_loader::exercise_config::{
+    find_config, visual_saccades::VisSaccadesConfig,
+};
+use crate::shared::{AppData, ProfileChange};
 use crate::widgets::evaluation::eval_config_widgets;
 use crate::widgets::exercise_config_menu::exercise_config_menu;
 use crate::widgets::{self};
@@ -15,7 +18,6 @@ use rand::{seq::SliceRandom, Rng};
 
 use super::ExerciseStage;
 
-#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct VisSaccades {
     session_status: ExerciseStage,
     arrow_pos: Option<Pos2>,
@@ -23,6 +25,7 @@ pub struct VisSaccades {
     response: Option<Direction>, // The given response is a direction
     exercise_params: VisSaccadesConfig,
     answer_timeout_timer: Timer,
+    trials: Vec<ArrowTrial>, // Every arrow shown and the response to it
     evaluation: Evaluation<f32>,
 }
 
@@ -35,6 +38,7 @@ impl Default for VisSaccades {
             answer: None,
             response: None,
             answer_timeout_timer: Timer::new(),
+            trials: vec![],
             evaluation: Evaluation::new(Duration::try_seconds(60).unwrap_or_default(), 60),
         }
     }
@@ -71,7 +75,7 @@ impl VisSaccades {
 
     /// Randomly position an arrow pointing in a random direction.
     fn new_arrow_pos(&mut self) {
-        let mut rng = rand::thread_rng();
+        let rng = self.evaluation.rng();
 
         if let Some(direction) = vec![
             Direction::Left,
@@ -79,7 +83,7 @@ impl VisSaccades {
             Direction::Up,
             Direction::Down,
         ]
-        .choose(&mut rng)
+        .choose(rng)
         {
             self.answer = Some(*direction);
         }
@@ -112,6 +116,7 @@ impl VisSaccades {
                 // If no arrow is visible, create new arrow and set answer timeout timer
                 if let None = self.answer {
                     self.new_arrow_pos();
+                    self.evaluation.set_stimulus_onset();
                     self.answer_timeout_timer.set(
                         Duration::try_milliseconds(self.exercise_params.answer_timeout)
                             .unwrap_or_default(),
@@ -133,7 +138,22 @@ impl VisSaccades {
     }
 
     fn next(&mut self) {
-        self.evaluation.add_result(self.evaluate_response());
+        if let (Some(pos), Some(answer)) = (self.arrow_pos, self.answer) {
+            self.trials.push(ArrowTrial {
+                pos,
+                answer,
+                response: self.response,
+            });
+        }
+        self.evaluation.set_trial_details(
+            serde_json::json!({ "position": self.arrow_pos, "direction": self.answer }),
+            self.response,
+        );
+        let result = self.evaluate_response();
+        match self.response {
+            Some(_) => self.evaluation.add_result(result),
+            None => self.evaluation.add_missed_result(result),
+        }
         self.answer = None;
         self.response = None;
     }
@@ -201,7 +221,16 @@ impl VisSaccades {
     }
 
     /// Review the evaluation.
-    fn finished_screen(&mut self, ui: &mut egui::Ui) {
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        if let Some(record) =
+            self.evaluation
+                .take_record(self.name(), &self.exercise_params.name, score)
+        {
+            let _ = appdata.history_tx.send(record);
+        }
+
         widgets::evaluation::post_eval_widgets(
             ui,
             self.evaluation.average_score(),
@@ -209,6 +238,24 @@ impl VisSaccades {
             self.evaluation.time_taken_as_string(),
         );
 
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        widgets::evaluation::trial_review(
+            ui,
+            vec2(160., 60.),
+            &self.trials,
+            &self.evaluation.scores(),
+            self.evaluation.show_timings(),
+            |painter, to_screen, trial| trial.paint(painter, to_screen),
+        );
+
+        widgets::evaluation::reaction_time_widgets(
+            ui,
+            self.evaluation.reaction_time_mean(),
+            self.evaluation.reaction_time_median(),
+            self.evaluation.reaction_time_variance(),
+        );
+
         // Close
         if ui.button("Close").clicked() {
             self.reset();
@@ -237,6 +284,44 @@ impl Exercise for VisSaccades {
         *self = Default::default();
     }
 
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        let exercise = appdata
+            .excconfig
+            .as_ref()
+            .and_then(|c| find_config(&c.visual_saccades, config))
+            .ok_or_else(|| format!("{} has no config named {}.", self.name(), config))?;
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        self.exercise_params = exercise.to_owned();
+        self.session_status = ExerciseStage::Response;
+        self.evaluation.start();
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.session_status == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+        self.answer_timeout_timer.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+        self.answer_timeout_timer.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
     fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut tts::Tts) {
         let menu_window = egui::Window::new(self.name())
             .anchor(
@@ -255,7 +340,7 @@ impl Exercise for VisSaccades {
             }
             // After an evaluation show the review
             ExerciseStage::Finished => {
-                menu_window.show(ctx, |ui| self.finished_screen(ui));
+                menu_window.show(ctx, |ui| self.finished_screen(ui, appdata));
             }
             // Any other status means we are in session.
             _ => {
@@ -276,9 +361,16 @@ impl Exercise for VisSaccades {
             [30, 120],
             [30, 120],
         );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
 
         // Display all exercise configs
+        let preferred = appdata.profiles.active().config(self.name());
         let mut func = |exercise: &VisSaccadesConfig| {
+            // Remember the chosen config for the active profile.
+            let _ = appdata.profile_tx.send(ProfileChange::Config {
+                exercise: self.name().to_owned(),
+                config: exercise.name.to_owned(),
+            });
             self.exercise_params = exercise.to_owned();
             self.session_status = ExerciseStage::Response;
             self.evaluation.start();
@@ -287,7 +379,7 @@ impl Exercise for VisSaccades {
         // Display exercise configs
         if let Some(config) = &appdata.excconfig {
             if let Some(config) =
-                exercise_config_menu::<VisSaccadesConfig>(ui, &config.visual_saccades, 3)
+                exercise_config_menu::<VisSaccadesConfig>(ui, &config.visual_saccades, 3, preferred)
             {
                 func(config)
             };
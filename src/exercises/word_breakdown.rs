@@ -0,0 +1,582 @@
+use crate::shared::asset_loader::sentences::{SentenceFile, Sentences};
+use crate::shared::{asset_loader, AppData, Evaluation, ProfileChange};
+use crate::widgets::{self, loading_screen, menu_button};
+use crate::wm::{Exercise, ExerciseType};
+use chrono::Duration;
+use egui::{vec2, Align, Color32, Key, RichText, TextEdit, Vec2};
+use rand::prelude::*;
+use tts::{self, Tts};
+
+use super::{alphabetical, reversed, ExerciseStage};
+
+/// Words shorter than this are too easy to break down.
+const MIN_WORD_LENGTH: usize = 7;
+
+/// The ways to break down a word.
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Breakdown {
+    Syllables,
+    Reversed,
+    Alphabetical,
+}
+
+impl std::fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Breakdown::Syllables => write!(f, "Syllables"),
+            Breakdown::Reversed => write!(f, "Reversed"),
+            Breakdown::Alphabetical => write!(f, "Alphabetical"),
+        }
+    }
+}
+
+impl Breakdown {
+    const ALL: [Breakdown; 3] = [
+        Breakdown::Syllables,
+        Breakdown::Reversed,
+        Breakdown::Alphabetical,
+    ];
+
+    fn instruction(&self) -> &'static str {
+        match self {
+            Breakdown::Syllables => "Type the syllables, separated by dashes or spaces.",
+            Breakdown::Reversed => "Type the letters in reverse order.",
+            Breakdown::Alphabetical => "Type the letters in alphabetical order.",
+        }
+    }
+
+    /// The answer to show for a word.
+    fn answer(&self, word: &str) -> String {
+        match self {
+            Breakdown::Syllables => syllables(word).join("-"),
+            Breakdown::Reversed => reversed(&word.chars().collect::<Vec<_>>())
+                .into_iter()
+                .collect(),
+            Breakdown::Alphabetical => alphabetical(&word.chars().collect::<Vec<_>>())
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Check a typed answer. Case and spaces between letters don't matter.
+    /// Syllables are checked loosely, because splitting words is a matter of
+    /// language rules and the rule of thumb in `syllables` is only a guide:
+    /// the syllables should spell the word, each have a vowel and be as
+    /// many as the rule of thumb finds.
+    fn check(&self, word: &str, answer: &str) -> bool {
+        let answer = answer.to_lowercase();
+        match self {
+            Breakdown::Syllables => {
+                let parts: Vec<&str> = answer
+                    .split(|c: char| c == '-' || c.is_whitespace())
+                    .filter(|part| !part.is_empty())
+                    .collect();
+                parts.concat() == word
+                    && parts.len() == syllables(word).len()
+                    && parts.iter().all(|part| part.chars().any(is_vowel))
+            }
+            _ => {
+                let letters: String = answer.chars().filter(|c| !c.is_whitespace()).collect();
+                letters == self.answer(word)
+            }
+        }
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiouyàáâäèéêëìíîïòóôöùúûü".contains(c)
+}
+
+/// Split a word into syllables with a rule of thumb: every group of vowels is
+/// the heart of a syllable. Of the consonants between two vowel groups, the last
+/// one starts the next syllable and the others close the previous one.
+fn syllables(word: &str) -> Vec<String> {
+    let letters: Vec<char> = word.chars().collect();
+
+    // Find where each group of vowels starts and ends
+    let mut groups: Vec<(usize, usize)> = vec![];
+    for (i, c) in letters.iter().enumerate() {
+        match (is_vowel(*c), groups.last_mut()) {
+            (true, Some((_, end))) if *end == i => *end = i + 1,
+            (true, _) => groups.push((i, i + 1)),
+            (false, _) => (),
+        }
+    }
+
+    let mut splits = vec![0];
+    for pair in groups.windows(2) {
+        let (end, start) = (pair[0].1, pair[1].0);
+        splits.push(match start - end {
+            0 | 1 => end,
+            _ => start - 1,
+        });
+    }
+    splits.push(letters.len());
+    splits
+        .windows(2)
+        .map(|split| letters[split[0]..split[1]].iter().collect())
+        .collect()
+}
+
+/// All long words in the sentences, without punctuation and in lower case.
+fn long_words(sentences: &[String]) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    for word in sentences.iter().flat_map(|s| s.split_whitespace()) {
+        let word: String = word
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .collect::<String>()
+            .to_lowercase();
+        if word.chars().count() >= MIN_WORD_LENGTH && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Breaking down words: the computer says a long word out loud. Break it down
+/// into its syllables, or reorder its letters.
+pub struct WordBreakdown {
+    stage: ExerciseStage,
+    breakdown: Breakdown,
+    sentences: Sentences,
+    words: Vec<String>,
+    word: String,
+    answer: String,
+    trials: Vec<(String, String)>, // Each word with the typed answer
+    evaluation: Evaluation<bool>,
+}
+
+impl Default for WordBreakdown {
+    fn default() -> Self {
+        Self {
+            stage: ExerciseStage::None,
+            breakdown: Breakdown::Syllables,
+            sentences: Sentences::default(),
+            words: vec![],
+            word: String::new(),
+            answer: String::new(),
+            trials: vec![],
+            evaluation: Evaluation::new(Duration::try_seconds(300).unwrap_or_default(), 10),
+        }
+    }
+}
+
+impl WordBreakdown {
+    /// Returns a boolean to indicate if the words are loaded.
+    /// If not, works to retrieve them from disk, web or the defaults, in that order.
+    fn contents_guarantee(&mut self, appdata: &AppData) -> bool {
+        if !self.words.is_empty() {
+            return true;
+        };
+
+        // If we don't have contents, we may have a promise for a web download
+        match &self.sentences.promise {
+            // No we don't have a promise
+            None => {
+                let Some(file) = &self.sentences.selected_file else {
+                    return false;
+                };
+                let Some(config) = &appdata.config else {
+                    return false;
+                };
+                let diskpath = format!(
+                    "{}{}{}",
+                    config.disk_root, config.sentences_path, file.filename
+                );
+                // Try to load contents of selected file from disk
+                match asset_loader::sentences::get_sentences_disk(diskpath) {
+                    Ok(lines) => self.sentences.contents = Some(lines),
+                    // Can't load from disk: create a promise to load from web
+                    Err(_) => {
+                        let webpath = format!(
+                            "{}{}{}",
+                            config.web_root, config.sentences_path, file.filename
+                        );
+                        self.sentences.promise =
+                            Some(asset_loader::sentences::get_sentences_web(webpath));
+                    }
+                };
+            }
+            // Yes, we have a promise
+            Some(promise) => match promise.ready() {
+                Some(Ok(resource)) => {
+                    self.sentences.contents = resource
+                        .text()
+                        .map(asset_loader::sentences::read_sentences_promise)
+                        .and_then(|lines| lines.ok());
+                }
+                Some(Err(e)) => {
+                    warn!("Failed to download sentences: {}", e);
+                    self.sentences.contents = Some(vec![]);
+                }
+                None => (),
+            },
+        }
+
+        // Take the long words from the sentences. Without any, fall back to the defaults.
+        if let Some(sentences) = &self.sentences.contents {
+            self.words = long_words(sentences);
+            if self.words.is_empty() {
+                self.words = long_words(&asset_loader::sentences::default_sentences());
+            }
+        }
+        !self.words.is_empty()
+    }
+
+    fn say(&self, spk: &mut tts::Tts) {
+        match spk.speak(&self.word, false) {
+            Ok(_) => debug!("TTS: Word spoken."),
+            Err(e) => warn!("TTS error: {:?}", e),
+        };
+    }
+
+    /// Pick a new word and say it, or finish when the session is over.
+    fn next_word(&mut self, spk: &mut tts::Tts) {
+        if self.evaluation.is_finished() {
+            self.stage = ExerciseStage::Finished;
+            return;
+        }
+        self.word = self
+            .words
+            .choose(self.evaluation.rng())
+            .cloned()
+            .unwrap_or_default();
+        self.answer.clear();
+        self.say(spk);
+        self.evaluation.set_stimulus_onset();
+        self.stage = ExerciseStage::Response;
+    }
+
+    /// Check the typed answer and show the right one.
+    fn submit_answer(&mut self) {
+        let answer = self.answer.trim().to_owned();
+        self.evaluation.set_trial_details(
+            serde_json::json!({ "word": self.word, "breakdown": self.breakdown.to_string() }),
+            &answer,
+        );
+        self.evaluation
+            .add_result(self.breakdown.check(&self.word, &answer));
+        self.trials.push((self.word.to_owned(), answer));
+        self.stage = ExerciseStage::Result;
+    }
+
+    /// Pick a file and start loading it.
+    fn select_file(&mut self, file: &SentenceFile) {
+        if self.sentences.selected_file.as_ref() != Some(file) {
+            self.sentences = Sentences::default();
+            self.words = vec![];
+            self.sentences.selected_file = Some(file.to_owned());
+        }
+        self.stage = ExerciseStage::Challenge;
+    }
+
+    /// Review the evaluation.
+    fn finished_screen(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        // Store the finished evaluation in the history.
+        let score = self.evaluation.average_score();
+        let language = self
+            .sentences
+            .selected_file
+            .as_ref()
+            .map(|file| file.language.to_owned())
+            .unwrap_or_default();
+        let config = format!("{}, {}", language, self.breakdown);
+        if let Some(record) = self.evaluation.take_record(self.name(), &config, score) {
+            let _ = appdata.history_tx.send(record);
+        }
+
+        widgets::evaluation::post_eval_widgets(
+            ui,
+            self.evaluation.average_score(),
+            self.evaluation.reps_done(),
+            self.evaluation.time_taken_as_string(),
+        );
+
+        widgets::evaluation::seed_label(ui, self.evaluation.seed());
+
+        // Show all answers
+        egui::Grid::new("word_breakdown_answers")
+            .striped(true)
+            .show(ui, |ui| {
+                for ((word, answer), correct) in
+                    self.trials.iter().zip(self.evaluation.show_results())
+                {
+                    ui.label(self.breakdown.answer(word));
+                    ui.label(answer);
+                    match correct {
+                        true => ui.colored_label(Color32::GREEN, "\u{2714}"),
+                        false => ui.colored_label(Color32::RED, "\u{2716}"),
+                    };
+                    ui.end_row();
+                }
+            });
+
+        // Close
+        if ui.button("Close").clicked() {
+            self.reset();
+        }
+    }
+}
+
+impl Exercise for WordBreakdown {
+    fn name(&self) -> &'static str {
+        "Word Breakdown"
+    }
+
+    fn description(&self) -> &'static str {
+        "Break down spoken words into syllables or letters."
+    }
+
+    fn help(&self) -> &'static str {
+        "This exercise uses your computers voice to say a long word out loud. Break the word down in your head and type the result: its syllables, its letters in reverse or its letters in alphabetical order.
+
+Pick a way to break down words and your language."
+    }
+
+    fn excercise_type(&self) -> Vec<ExerciseType> {
+        vec![ExerciseType::Cognitive]
+    }
+
+    fn reset(&mut self) {
+        *self = WordBreakdown::default();
+    }
+
+    /// Configs name the way to break down words, e.g. "Reversed".
+    /// The words are in the language preferred by the active profile.
+    fn start_with(
+        &mut self,
+        appdata: &AppData,
+        config: &str,
+        duration: Duration,
+        reps: usize,
+    ) -> Result<(), String> {
+        self.breakdown = *Breakdown::ALL
+            .iter()
+            .find(|b| b.to_string() == config)
+            .ok_or(format!("{}: unknown config {}.", self.name(), config))?;
+        let files = match &appdata.config {
+            Some(config) => &config.sentences_files,
+            None => return Err(format!("{}: no sentences available.", self.name())),
+        };
+        let preferred = appdata.profiles.active().language.as_deref();
+        let file = files
+            .iter()
+            .find(|f| Some(f.language.as_str()) == preferred)
+            .or(files.first())
+            .ok_or(format!("{}: no sentences available.", self.name()))?;
+        self.select_file(file);
+        self.evaluation.duration = duration;
+        self.evaluation.set_reps(reps);
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.stage == ExerciseStage::Finished
+    }
+
+    fn pause(&mut self) {
+        self.evaluation.pause();
+    }
+
+    fn resume(&mut self) {
+        self.evaluation.resume();
+    }
+
+    fn trial_scores(&self) -> Vec<f32> {
+        self.evaluation.scores()
+    }
+
+    fn show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        let window = egui::Window::new(self.name())
+            .anchor(
+                egui::Align2([Align::Center, Align::TOP]),
+                Vec2::new(0., 100.),
+            )
+            .fixed_size(vec2(350., 300.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false);
+
+        match self.stage {
+            ExerciseStage::None => {
+                window.show(ctx, |ui| self.ui(ui, appdata, tts));
+            }
+            ExerciseStage::Finished => {
+                window.show(ctx, |ui| self.finished_screen(ui, appdata));
+            }
+            _ => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                egui::CentralPanel::default().show(ctx, |ui| self.session(ui, appdata, tts));
+            }
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
+        ui.label(self.help());
+        ui.separator();
+
+        widgets::evaluation::eval_config_widgets(
+            ui,
+            &mut self.evaluation.duration,
+            &mut self.evaluation.repetitions,
+            [60, 600],
+            [5, 30],
+        );
+        widgets::evaluation::seed_widgets(ui, &mut self.evaluation.replay_seed);
+
+        ui.horizontal(|ui| {
+            for breakdown in Breakdown::ALL {
+                ui.selectable_value(&mut self.breakdown, breakdown, breakdown.to_string());
+            }
+        });
+
+        // Highlight the language preferred by the active profile.
+        let preferred = appdata.profiles.active().language.as_deref();
+        if let Some(config) = &appdata.config {
+            for file in &config.sentences_files {
+                let override_color = match preferred == Some(file.language.as_str()) {
+                    true => Some(ui.visuals().selection.bg_fill),
+                    false => None,
+                };
+                if menu_button(ui, None, override_color, file.language.as_str(), "").clicked() {
+                    // Remember the chosen language for the active profile.
+                    let _ = appdata
+                        .profile_tx
+                        .send(ProfileChange::Language(file.language.to_owned()));
+                    self.select_file(file);
+                };
+            }
+        }
+    }
+
+    fn session(&mut self, ui: &mut egui::Ui, appdata: &AppData, tts: &mut Tts) {
+        // Load the words before starting
+        if self.stage == ExerciseStage::Challenge {
+            if !self.contents_guarantee(appdata) {
+                loading_screen(ui);
+                if ui.button("Cancel").clicked() {
+                    self.reset();
+                }
+                return;
+            }
+            self.evaluation.start();
+            self.next_word(tts);
+        }
+
+        // Stop when the time is up, even while typing. An unanswered word isn't counted.
+        // After an answer, the result is shown first: the next word finishes the session.
+        if self.stage == ExerciseStage::Response && self.evaluation.is_finished() {
+            self.stage = ExerciseStage::Finished;
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.reset();
+            };
+            ui.label(format!(
+                "Time remaining: {}",
+                self.evaluation.time_remaining_as_string()
+            ));
+            ui.label(format!(
+                "Words remaining: {}",
+                self.evaluation.reps_remaining()
+            ));
+        });
+
+        let spacer = ui.available_height() / 30.;
+        ui.vertical_centered(|ui| {
+            ui.add_space(spacer * 4.);
+            ui.label(self.breakdown.instruction());
+            ui.add_space(spacer);
+
+            match self.stage {
+                ExerciseStage::Response => {
+                    let input = ui.add(
+                        TextEdit::singleline(&mut self.answer)
+                            .font(egui::TextStyle::Heading)
+                            .desired_width(300.),
+                    );
+                    if input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                        self.submit_answer();
+                    }
+                    input.request_focus();
+
+                    ui.add_space(spacer);
+                    if ui
+                        .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Repeat"))
+                        .clicked()
+                    {
+                        self.say(tts);
+                    };
+                    ui.add_space(spacer / 4.);
+                    if ui
+                        .add_sized(vec2(spacer * 4., spacer * 2.), egui::Button::new("Check"))
+                        .clicked()
+                    {
+                        self.submit_answer();
+                    };
+                    ui.add_space(spacer);
+                    ui.label("Press return to check your answer.");
+                }
+                ExerciseStage::Result => {
+                    let (text, color) = match self.evaluation.show_results().last() {
+                        Some(true) => ("Correct!", Color32::GREEN),
+                        _ => ("Not quite.", Color32::RED),
+                    };
+                    ui.label(RichText::new(text).size(25.).color(color));
+                    ui.label("The word was");
+                    ui.heading(RichText::new(&self.word).size(25.));
+                    ui.label(self.breakdown.to_string());
+                    ui.heading(RichText::new(self.breakdown.answer(&self.word)).size(25.));
+
+                    ui.add_space(spacer);
+                    if ui
+                        .add_sized(
+                            vec2(spacer * 4., spacer * 2.),
+                            egui::Button::new("Continue"),
+                        )
+                        .clicked()
+                        || ui.input(|i| i.key_pressed(Key::Space))
+                    {
+                        self.next_word(tts);
+                    };
+                    ui.add_space(spacer);
+                    ui.label("Press space to continue.");
+                }
+                _ => (),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_syllables() {
+        assert_eq!(syllables("gisteren"), vec!["gis", "te", "ren"]);
+        assert_eq!(syllables("wandelen"), vec!["wan", "de", "len"]);
+        assert_eq!(syllables("computer"), vec!["com", "pu", "ter"]);
+        assert_eq!(syllables("rhythm"), vec!["rhythm"]);
+    }
+
+    #[test]
+    fn check_answers() {
+        assert!(Breakdown::Syllables.check("vandaag", "Van-daag"));
+        assert!(Breakdown::Syllables.check("vandaag", "vand aag"));
+        assert!(!Breakdown::Syllables.check("vandaag", "va nd aag"));
+        assert!(!Breakdown::Syllables.check("vandaag", "vandaag"));
+        assert!(Breakdown::Reversed.check("vandaag", "gaadnav"));
+        assert!(Breakdown::Alphabetical.check("vandaag", "a a a d g n v"));
+        assert!(!Breakdown::Alphabetical.check("vandaag", "aaadgvn"));
+    }
+
+    #[test]
+    fn long_words_only() {
+        let sentences = vec![String::from("Hij sport in de sportschool, vandaag!")];
+        assert_eq!(long_words(&sentences), vec!["sportschool", "vandaag"]);
+    }
+}
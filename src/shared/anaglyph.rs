@@ -89,14 +89,14 @@ impl Anaglyph {
     /// - Generate random arrays of 1's and 0's for left and right backgrounds.
     /// - Calculate a diamond shape for the focal glyphs.
     /// - Remove the 'background' to the focal glyphs to create depth illusion (occlusion).
-    pub fn initialize(&mut self) {
+    pub fn initialize<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         // Create necessary arrays
         let distr = Binomial::new(1, 0.5).unwrap();
-        self.arrays.background_left = Array2::random((self.grid_size, self.grid_size), distr);
+        self.arrays.background_left =
+            Array2::random_using((self.grid_size, self.grid_size), distr, rng);
         self.arrays.background_right = self.arrays.background_left.clone();
-        self.arrays.focal = Array2::random((self.grid_size, self.grid_size), distr);
+        self.arrays.focal = Array2::random_using((self.grid_size, self.grid_size), distr, rng);
         self.arrays.focal_mask = Array2::zeros((self.grid_size, self.grid_size));
-        let mut rng = thread_rng();
         self.focal_position = match rng.gen_range(0..=3) {
             0 => Direction::Up,
             1 => Direction::Left,
@@ -294,7 +294,8 @@ impl Anaglyph {
         Ok(())
     }
 
-    /// Show some simple debugging controls
+    /// Show some simple debugging controls.
+    /// Debugging doesn't need reproducible stimuli, so these use the thread rng.
     pub fn debug_controls(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             ui.add(egui::Checkbox::new(&mut self.debug.draw_left, "Left"));
@@ -310,13 +311,13 @@ impl Anaglyph {
                 .add(egui::Slider::new(&mut self.pixel_size, 1..=10).suffix("pixel size"))
                 .changed()
             {
-                self.initialize()
+                self.initialize(&mut thread_rng())
             };
             if ui
                 .add(egui::Slider::new(&mut self.grid_size, 10..=150).suffix("anaglyph size"))
                 .changed()
             {
-                self.initialize()
+                self.initialize(&mut thread_rng())
             };
             if ui
                 .add(
@@ -325,13 +326,13 @@ impl Anaglyph {
                 )
                 .changed()
             {
-                self.initialize()
+                self.initialize(&mut thread_rng())
             };
             if ui
                 .add(egui::Slider::new(&mut self.focal_offset, 0..=10).suffix("focal_offset"))
                 .changed()
             {
-                self.initialize()
+                self.initialize(&mut thread_rng())
             };
         });
     }
@@ -1,11 +1,20 @@
 use egui::Color32;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
 pub struct AnaglyphColor {
     pub left: Color32,
     pub right: Color32,
     pub calibration_left: Color32,
     pub calibration_right: Color32,
+    /// Did the colors in use pass the crosstalk check?
+    #[serde(default)]
+    pub checked: bool,
+    /// Crosstalk check of the calibration colors: does each eye see only its own pattern?
+    #[serde(skip)]
+    pub check_left: bool,
+    #[serde(skip)]
+    pub check_right: bool,
 }
 
 impl Default for AnaglyphColor {
@@ -15,6 +24,9 @@ impl Default for AnaglyphColor {
             right: Color32::from_rgba_unmultiplied(255, 25, 25, 50), // red
             calibration_left: Color32::from_rgba_unmultiplied(0, 38, 230, 100), // blue,
             calibration_right: Color32::from_rgba_unmultiplied(255, 25, 25, 50), // red,
+            checked: false,
+            check_left: false,
+            check_right: false,
         }
     }
 }
@@ -24,12 +36,15 @@ impl AnaglyphColor {
     pub fn reset_calibration_values(&mut self) {
         self.calibration_left = self.left.clone();
         self.calibration_right = self.right.clone();
+        self.check_left = self.checked;
+        self.check_right = self.checked;
     }
 
     /// Save calibrated values and stop calibration
     pub fn save_calibration_colors(&mut self) {
         self.left = self.calibration_left;
         self.right = self.calibration_right;
+        self.checked = self.check_left && self.check_right;
     }
 
     /// Swap right and left eye colors
@@ -37,5 +52,26 @@ impl AnaglyphColor {
         let tmp = self.calibration_left;
         self.calibration_left = self.calibration_right;
         self.calibration_right = tmp;
+        self.reset_check();
+    }
+
+    /// The calibration colors changed, so the crosstalk check has to be done again.
+    pub fn reset_check(&mut self) {
+        self.check_left = false;
+        self.check_right = false;
+    }
+}
+
+/// Identifies the display the app is shown on, so calibrations can be stored per display.
+/// Colors look different on each screen, so a calibration only holds for one display.
+pub fn display_id(ctx: &egui::Context) -> String {
+    let pixels_per_point = ctx.pixels_per_point();
+    match ctx.input(|i| i.viewport().monitor_size) {
+        Some(size) => format!(
+            "{:.0}x{:.0}",
+            size.x * pixels_per_point,
+            size.y * pixels_per_point
+        ),
+        None => String::from("Unknown display"),
     }
 }
@@ -3,8 +3,12 @@ use std::sync::mpsc::{self, Receiver, Sender};
 use ehttp::Response;
 use poll_promise::Promise;
 
-use super::asset_loader::{
-    exercise_config_collection::ExerciseConfigCollection, perhabs_config::PerhabsConfig,
+use super::{
+    asset_loader::{
+        exercise_config_collection::ExerciseConfigCollection, perhabs_config::PerhabsConfig,
+        programs::ProgramCollection,
+    },
+    AnaglyphColor, History, ProfileChange, Profiles, SessionRecord,
 };
 
 /// AppData is loaded when launching Perhabs. Individual modules/windows get app-wide
@@ -15,23 +19,57 @@ pub struct AppData {
     pub config_promise: Option<Promise<ehttp::Result<Response>>>,
     pub excconfig: Option<ExerciseConfigCollection>,
     pub excconfig_promise: Option<Promise<ehttp::Result<Response>>>,
+    pub programs: Option<ProgramCollection>,
+    pub programs_promise: Option<Promise<ehttp::Result<Response>>>,
     pub debug_messages: Vec<String>,
     pub error_tx: Sender<String>,
     pub error_rx: Receiver<String>,
+    pub history: History,
+    pub history_tx: Sender<SessionRecord>,
+    pub history_rx: Receiver<SessionRecord>,
+    pub profiles: Profiles,
+    pub profile_tx: Sender<ProfileChange>,
+    pub profile_rx: Receiver<ProfileChange>,
+    /// The display the app is shown on, see `shared::display_id`.
+    pub display: String,
 }
 
 impl Default for AppData {
     fn default() -> Self {
         let (error_tx, error_rx) = mpsc::channel();
+        let (history_tx, history_rx) = mpsc::channel();
+        let (profile_tx, profile_rx) = mpsc::channel();
+        let profiles = Profiles::load();
+        let (mut history, history_error) = History::load();
+        history.set_profile(&profiles.active().name);
+        if let Some(e) = history_error {
+            let _ = error_tx.send(e);
+        }
         Self {
             debug: false,
             config: None,
             config_promise: None,
             excconfig: None,
             excconfig_promise: None,
+            programs: None,
+            programs_promise: None,
             debug_messages: vec![],
             error_tx,
             error_rx,
+            history,
+            history_tx,
+            history_rx,
+            profiles,
+            profile_tx,
+            profile_rx,
+            display: String::new(),
         }
     }
 }
+
+impl AppData {
+    /// The anaglyph calibration of the active profile for the current display.
+    pub fn anaglyph_color(&self) -> AnaglyphColor {
+        self.profiles.active().calibration(&self.display).clone()
+    }
+}
@@ -9,7 +9,9 @@ use serde::{Deserialize, Serialize};
 pub mod exercise_config;
 pub mod exercise_config_collection;
 pub mod perhabs_config;
+pub mod programs;
 pub mod sentences;
+pub mod word_categories;
 
 /// Describes where an asset was loaded from.
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -36,6 +38,7 @@ pub fn write_string_to_file(filepath: &Path, content: String) -> Result<(), io::
     match OpenOptions::new()
         .append(false)
         .write(true)
+        .truncate(true)
         .create(true)
         .open(filepath)
     {
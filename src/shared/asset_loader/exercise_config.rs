@@ -6,3 +6,8 @@ pub mod visual_saccades;
 pub trait ExerciseConfig {
     fn name(&self) -> &str;
 }
+
+/// Find a config by its name.
+pub fn find_config<'a, T: ExerciseConfig>(configs: &'a [T], name: &str) -> Option<&'a T> {
+    configs.iter().find(|c| c.name() == name)
+}
@@ -19,10 +19,20 @@ pub struct PerhabsConfig {
     pub web_root: String,
     pub config_path: String,
     pub excconfig_path: String,
+    #[serde(default = "default_programs_path")]
+    pub programs_path: String,
     pub sentences_path: String,
     pub sentences_files: Vec<SentenceFile>,
     pub episodic_memory_path: String,
     pub episodic_memory_files: Vec<SentenceFile>,
+    #[serde(default = "default_word_categories_path")]
+    pub word_categories_path: String,
+    #[serde(default = "default_word_categories_files")]
+    pub word_categories_files: Vec<SentenceFile>,
+    #[serde(default = "default_lingo_path")]
+    pub lingo_path: String,
+    #[serde(default = "default_lingo_files")]
+    pub lingo_files: Vec<SentenceFile>,
     pub source: AssetSource,
 }
 
@@ -36,6 +46,7 @@ impl Default for PerhabsConfig {
             // there is no way to know where to get this data...
             config_path: String::from("appdata/config.json"),
             excconfig_path: String::from("appdata/exercise_configs.json"),
+            programs_path: default_programs_path(),
             sentences_path: String::from("excdata/sentences/"),
             sentences_files: vec![SentenceFile {
                 filename: String::from("sentences_EN.txt"),
@@ -46,11 +57,56 @@ impl Default for PerhabsConfig {
                 filename: String::from("episodic_memory_EN.txt"),
                 language: String::from("English"),
             }],
+            word_categories_path: default_word_categories_path(),
+            word_categories_files: default_word_categories_files(),
+            lingo_path: default_lingo_path(),
+            lingo_files: default_lingo_files(),
             source: AssetSource::Default,
         }
     }
 }
 
+/// Configs from before programs existed don't have a programs path.
+fn default_programs_path() -> String {
+    String::from("appdata/programs.json")
+}
+
+/// Configs from before Scattergories existed don't have word categories.
+fn default_word_categories_path() -> String {
+    String::from("excdata/")
+}
+
+fn default_word_categories_files() -> Vec<SentenceFile> {
+    vec![
+        SentenceFile {
+            filename: String::from("word_categories_EN.txt"),
+            language: String::from("English"),
+        },
+        SentenceFile {
+            filename: String::from("word_categories_NL.txt"),
+            language: String::from("Nederlands"),
+        },
+    ]
+}
+
+/// Configs from before Lingo existed don't have word lists for Lingo.
+fn default_lingo_path() -> String {
+    String::from("excdata/lingo/")
+}
+
+fn default_lingo_files() -> Vec<SentenceFile> {
+    vec![
+        SentenceFile {
+            filename: String::from("lingo_EN.txt"),
+            language: String::from("English"),
+        },
+        SentenceFile {
+            filename: String::from("lingo_NL.txt"),
+            language: String::from("Nederlands"),
+        },
+    ]
+}
+
 impl PerhabsConfig {
     pub fn from_disk() -> io::Result<Self> {
         debug!("Getting Perhabs config: trying disk.");
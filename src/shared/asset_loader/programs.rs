@@ -0,0 +1,72 @@
+use ehttp::{Response, Result};
+use log::debug;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fs::File,
+    io::{self, BufReader},
+};
+
+use super::AssetSource;
+
+/// A single exercise in a program: which exercise to run, with which config
+/// and for how long.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ProgramStep {
+    /// The name of the exercise, as shown in the exercise menu.
+    pub exercise: String,
+    /// The name of the exercise config, e.g. "Easy (tiny)" or "6 numbers".
+    pub config: String,
+    pub duration_secs: i64,
+    pub reps: usize,
+}
+
+/// A training program: an ordered list of exercises that are run back to back.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Program {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<ProgramStep>,
+}
+
+/// ProgramCollection
+/// All training programs, loaded from disk or web like the ExerciseConfigCollection.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ProgramCollection {
+    pub source: AssetSource,
+    pub programs: Vec<Program>,
+}
+
+impl Default for ProgramCollection {
+    fn default() -> Self {
+        debug!("Getting programs: falling back to default.");
+        Self {
+            source: AssetSource::Default,
+            programs: vec![],
+        }
+    }
+}
+
+impl ProgramCollection {
+    pub fn from_disk(path: &String) -> io::Result<ProgramCollection> {
+        debug!("Getting programs: trying disk.");
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let mut de = serde_json::Deserializer::from_reader(reader);
+        let programs = ProgramCollection::deserialize(&mut de)?;
+        Ok(programs)
+    }
+
+    pub fn from_web(path: &String) -> Promise<Result<Response>> {
+        debug!("Getting programs: trying web.");
+        let (sender, promise) = Promise::new();
+        let request = ehttp::Request::get(path);
+        ehttp::fetch(request, move |response| {
+            sender.send(response);
+        });
+
+        promise
+    }
+}
@@ -28,10 +28,9 @@ impl Default for Sentences {
 
 impl Sentences {
     /// Shuffle the file contents vec using the Fisher-Yates shuffle algorithm.
-    pub fn shuffle_contents(&mut self) {
+    pub fn shuffle_contents<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         if let Some(contents) = &mut self.contents {
             let length = contents.len();
-            let mut rng = thread_rng();
             for i in 0..length {
                 let j = rng.gen_range(i..length);
                 let tmp = contents[i].clone();
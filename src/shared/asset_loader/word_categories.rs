@@ -0,0 +1,77 @@
+use log::debug;
+
+/// Parse the lines of a word categories file into lists of categories.
+/// A file either holds a single list with one category per line, or several
+/// numbered lists that each start with a header like "LIST ONE:".
+pub fn parse_word_categories(lines: &[String]) -> Vec<Vec<String>> {
+    let mut lists = vec![];
+    let mut list: Vec<String> = vec![];
+
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // A header starts a new list
+        if line.ends_with(':') {
+            if !list.is_empty() {
+                lists.push(list);
+                list = vec![];
+            }
+            continue;
+        }
+        // Strip the numbering, e.g. "12. Tools"
+        let category = match line.split_once(". ") {
+            Some((number, category)) if number.chars().all(|c| c.is_ascii_digit()) => category,
+            _ => line,
+        };
+        list.push(String::from(category.trim()));
+    }
+    if !list.is_empty() {
+        lists.push(list);
+    }
+    lists
+}
+
+pub fn default_word_categories() -> Vec<Vec<String>> {
+    debug!("Getting word categories: falling back to default.");
+    let categories = vec![
+        "A boy's name",
+        "A river",
+        "An animal",
+        "Things that are cold",
+        "Insects",
+        "TV Shows",
+        "Things that grow",
+        "Fruits",
+        "Things that are black",
+        "School subjects",
+        "Movie titles",
+        "Musical Instruments",
+    ];
+    vec![categories.into_iter().map(String::from).collect()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_lists() {
+        let numbered = lines("LIST ONE:\n1. A boy’s name\n2. A river\n\n\nLIST TWO:\n1. Authors\n");
+        assert_eq!(
+            parse_word_categories(&numbered),
+            vec![vec!["A boy’s name", "A river"], vec!["Authors"]]
+        );
+
+        let single = lines("Dieren\nFruit\nBands/artiesten");
+        assert_eq!(
+            parse_word_categories(&single),
+            vec![vec!["Dieren", "Fruit", "Bands/artiesten"]]
+        );
+    }
+}
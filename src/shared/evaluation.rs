@@ -1,10 +1,47 @@
-use crate::shared::timer::Timer;
-use chrono::{Duration, Local};
+use std::sync::Arc;
+
+use crate::shared::{
+    history::SessionRecord,
+    stats,
+    timer::{Clock, MonotonicClock, Timer},
+};
+use chrono::{DateTime, Duration, Local};
 use egui::Vec2;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Timing of a single trial: when the stimulus was shown and when the response came in.
+/// Either may be missing: not every exercise marks the stimulus onset and a trial
+/// can time out without a response.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct TrialTiming {
+    pub onset: Option<DateTime<Local>>,
+    pub response: Option<DateTime<Local>>,
+}
+
+impl TrialTiming {
+    /// Time between stimulus onset and response.
+    /// Returns None if either is missing.
+    pub fn reaction_time(&self) -> Option<Duration> {
+        Some(self.response? - self.onset?)
+    }
+}
+
+/// What was shown and what the user answered in a single trial, for exporting raw data.
+/// Each exercise describes its own stimuli and responses, e.g. the position and
+/// direction of an arrow. Either is null if the exercise doesn't describe it.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct TrialDetails {
+    pub stimulus: serde_json::Value,
+    pub response: serde_json::Value,
+}
 
 /// Manage a performance evaluation by keeping track of time and reps and
 /// storing results.
 ///
+/// Each evaluation has a random number generator for its stimuli, seeded when
+/// the evaluation starts. Setting `replay_seed` repeats the stimuli of an earlier session.
+///
 /// Note: comparing two Evaluation structs only compares the duration and
 /// repetitions fields!
 pub struct Evaluation<T> {
@@ -13,7 +50,25 @@ pub struct Evaluation<T> {
     pub duration: Duration,
     pub repetitions: usize,
     timer: Timer,
+    /// Times trials, so reaction times don't jump when the system time changes.
+    clock: Arc<dyn Clock>,
+    /// The clock's time at `start_time`. Trial timestamps are counted from there.
+    clock_start: Duration,
     results: Vec<T>,
+    timings: Vec<TrialTiming>,
+    details: Vec<TrialDetails>,
+    stimulus_onset: Option<DateTime<Local>>,
+    response_time: Option<DateTime<Local>>,
+    next_details: TrialDetails,
+    paused_at: Option<Duration>,
+    /// Total time spent paused. The start and end times are wall-clock times, so
+    /// this is subtracted from the time taken.
+    paused: Duration,
+    recorded: bool,
+    /// The seed to use for the next session. A new seed is picked if None.
+    pub replay_seed: Option<u64>,
+    seed: u64,
+    rng: StdRng,
 }
 
 impl<T> PartialEq for Evaluation<T> {
@@ -28,13 +83,31 @@ impl<T> PartialEq for Evaluation<T> {
 
 impl<T> Evaluation<T> {
     pub fn new(duration: Duration, repetitions: usize) -> Self {
+        Self::with_clock(duration, repetitions, Arc::new(MonotonicClock))
+    }
+
+    /// Create an evaluation that measures its duration with the given clock.
+    pub fn with_clock(duration: Duration, repetitions: usize, clock: Arc<dyn Clock>) -> Self {
         Self {
             start_time: chrono::Local::now(),
             end_time: None,
             duration,
             repetitions,
-            timer: Timer::new(),
+            timer: Timer::with_clock(clock.clone()),
+            clock_start: clock.now(),
+            clock,
             results: vec![],
+            timings: vec![],
+            details: vec![],
+            stimulus_onset: None,
+            response_time: None,
+            next_details: TrialDetails::default(),
+            paused_at: None,
+            paused: Duration::zero(),
+            recorded: false,
+            replay_seed: None,
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
         }
     }
 
@@ -45,8 +118,54 @@ impl<T> Evaluation<T> {
     /// Start evaluation
     pub fn start(&mut self) {
         self.end_time = None;
+        self.recorded = false;
+        self.stimulus_onset = None;
+        self.response_time = None;
+        self.next_details = TrialDetails::default();
+        self.paused_at = None;
+        self.paused = Duration::zero();
         self.start_time = chrono::Local::now();
+        self.clock_start = self.clock.now();
         self.timer.set(self.duration);
+        // Seeds are kept short, so they are easy to write down and type in.
+        self.seed = self
+            .replay_seed
+            .unwrap_or_else(|| rand::random::<u32>() as u64);
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+
+    /// The seed of the current session.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The random number generator for the stimuli of the current session.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// Pause the evaluation. Time spent paused doesn't count towards the duration,
+    /// the time taken or reaction times.
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(self.clock.now());
+            self.timer.pause();
+        }
+    }
+
+    /// Resume a paused evaluation.
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            let paused = self.clock.now() - paused_at;
+            self.paused += paused;
+            if let Some(onset) = &mut self.stimulus_onset {
+                *onset += paused;
+            }
+            if let Some(response) = &mut self.response_time {
+                *response += paused;
+            }
+            self.timer.resume();
+        }
     }
 
     /// Set duration from number of seconds.
@@ -59,9 +178,58 @@ impl<T> Evaluation<T> {
         self.repetitions = reps;
     }
 
-    /// Add result of type T
+    /// Mark the moment the stimulus for the next trial is shown.
+    /// The next result is timed from this moment.
+    pub fn set_stimulus_onset(&mut self) {
+        self.stimulus_onset = Some(self.timestamp());
+        self.response_time = None;
+    }
+
+    /// Mark the moment of the response to the current trial, for results that are
+    /// added later, e.g. when the trial ends. Only the first response counts.
+    pub fn set_response_time(&mut self) {
+        if self.response_time.is_none() {
+            self.response_time = Some(self.timestamp());
+        }
+    }
+
+    /// Describe the stimulus and response of the next trial. Call before adding its result.
+    pub fn set_trial_details(&mut self, stimulus: impl Serialize, response: impl Serialize) {
+        self.next_details = TrialDetails {
+            stimulus: serde_json::to_value(stimulus).unwrap_or_default(),
+            response: serde_json::to_value(response).unwrap_or_default(),
+        };
+    }
+
+    /// Add result of type T, responded to just now or at the response time that was set.
     pub fn add_result(&mut self, result: T) {
+        let response = self
+            .response_time
+            .take()
+            .unwrap_or_else(|| self.timestamp());
+        self.push_result(result, Some(response));
+    }
+
+    /// Add result of type T for a trial without a response, e.g. when the
+    /// answer timed out. The trial doesn't count towards reaction times.
+    pub fn add_missed_result(&mut self, result: T) {
+        self.response_time = None;
+        self.push_result(result, None);
+    }
+
+    /// The time of a trial event. Measured with the evaluation's clock from the
+    /// start of the session, so only the start itself depends on the system time.
+    fn timestamp(&self) -> DateTime<Local> {
+        self.start_time + (self.clock.now() - self.clock_start)
+    }
+
+    fn push_result(&mut self, result: T, response: Option<DateTime<Local>>) {
         self.results.push(result);
+        self.timings.push(TrialTiming {
+            onset: self.stimulus_onset.take(),
+            response,
+        });
+        self.details.push(std::mem::take(&mut self.next_details));
     }
 
     /// Return a vec of all results.
@@ -69,6 +237,11 @@ impl<T> Evaluation<T> {
         &self.results
     }
 
+    /// Return the timing of each result, in the same order as the results.
+    pub fn show_timings(&self) -> &Vec<TrialTiming> {
+        &self.timings
+    }
+
     /// Are we done?
     pub fn is_finished(&mut self) -> bool {
         // Have we recorded an end time?
@@ -77,12 +250,12 @@ impl<T> Evaluation<T> {
         }
         // Amount of reps done?
         if self.results.len() >= self.repetitions {
-            self.end_time = Some(chrono::Local::now());
+            self.end_time = Some(self.timestamp());
             return true;
         };
         // Time up?
         if self.timer.is_finished() {
-            self.end_time = Some(chrono::Local::now());
+            self.end_time = Some(self.timestamp());
             return true;
         };
         // default: we are not finished
@@ -118,11 +291,11 @@ impl<T> Evaluation<T> {
         self.format_min_secs(&self.timer.remaining())
     }
 
-    /// How much time did we take?
+    /// How much time did we take, without the time spent paused?
     /// Returns None if not finished.
     pub fn time_taken(&self) -> Option<Duration> {
         if let Some(end_time) = self.end_time {
-            Some(end_time - self.start_time)
+            Some(end_time - self.start_time - self.paused)
         } else {
             None
         }
@@ -158,6 +331,76 @@ impl<T> Evaluation<T> {
     pub fn reps_remaining(&self) -> usize {
         self.repetitions.saturating_sub(self.reps_done())
     }
+
+    /// Reaction times in milliseconds of all trials with both a stimulus onset and a response.
+    pub fn reaction_times_ms(&self) -> Vec<f32> {
+        self.timings
+            .iter()
+            .filter_map(|t| t.reaction_time())
+            .map(|d| d.num_milliseconds() as f32)
+            .collect()
+    }
+
+    /// Mean reaction time in milliseconds.
+    /// Returns None if no reaction times were recorded.
+    pub fn reaction_time_mean(&self) -> Option<f32> {
+        stats::mean(&self.reaction_times_ms())
+    }
+
+    /// Median reaction time in milliseconds.
+    /// Returns None if no reaction times were recorded.
+    pub fn reaction_time_median(&self) -> Option<f32> {
+        stats::median(&self.reaction_times_ms())
+    }
+
+    /// Variance of the reaction times in milliseconds squared.
+    /// Returns None if no reaction times were recorded.
+    pub fn reaction_time_variance(&self) -> Option<f32> {
+        stats::variance(&self.reaction_times_ms())
+    }
+}
+
+// ***********
+// History
+// ***********
+
+impl<T: Serialize> Evaluation<T> {
+    /// Create a record of this evaluation for the training history.
+    /// Only returns a record once per evaluation and only when the evaluation
+    /// is finished and has results. Returns None otherwise.
+    pub fn take_record(
+        &mut self,
+        exercise: &str,
+        config: &str,
+        score: f32,
+    ) -> Option<SessionRecord> {
+        if self.recorded || self.results.is_empty() || !self.is_finished() {
+            return None;
+        }
+        let end_time = self.end_time?;
+        self.recorded = true;
+
+        Some(SessionRecord {
+            // The history sets the profile when the record is added.
+            profile: String::new(),
+            exercise: String::from(exercise),
+            config: String::from(config),
+            start_time: self.start_time,
+            end_time,
+            paused_ms: self.paused.num_milliseconds(),
+            repetitions: self.repetitions,
+            reps_done: self.reps_done(),
+            results: self
+                .results
+                .iter()
+                .filter_map(|r| serde_json::to_value(r).ok())
+                .collect(),
+            timings: self.timings.clone(),
+            details: self.details.clone(),
+            seed: Some(self.seed),
+            score,
+        })
+    }
 }
 
 // ***********
@@ -218,22 +461,106 @@ impl Evaluation<f32> {
 }
 
 impl Evaluation<Vec2> {
+    /// The average score of the offsets, from 0 to 1. See `TrialScore` for `Vec2`.
     pub fn average_score(&self) -> f32 {
-        // Calculate average score.
-        let mut total_score = Vec2::new(0., 0.);
-        for r in self.show_results() {
-            total_score.x += r.x;
-            total_score.y += r.y;
+        stats::mean(&self.scores()).unwrap_or_default()
+    }
+
+    /// The average distance of the offsets. Offsets in opposite directions
+    /// don't cancel out: use `bias` for that.
+    pub fn mean_error(&self) -> f32 {
+        stats::mean(&self.errors()).unwrap_or_default()
+    }
+
+    /// The average offset in each direction: a systematic shift to one side.
+    pub fn bias(&self) -> Vec2 {
+        let xs: Vec<f32> = self.show_results().iter().map(|r| r.x).collect();
+        let ys: Vec<f32> = self.show_results().iter().map(|r| r.y).collect();
+        Vec2::new(
+            stats::mean(&xs).unwrap_or_default(),
+            stats::mean(&ys).unwrap_or_default(),
+        )
+    }
+
+    /// The distance of each offset, regardless of direction.
+    pub fn errors(&self) -> Vec<f32> {
+        self.show_results().iter().map(|r| r.length()).collect()
+    }
+}
+
+// ***********
+// Statistics
+// ***********
+
+/// A result that can be expressed as a single score, so the results of an
+/// evaluation can be summarized with the functions in `stats`.
+pub trait TrialScore {
+    fn score(&self) -> f32;
+}
+
+impl TrialScore for bool {
+    fn score(&self) -> f32 {
+        match self {
+            true => 1.,
+            false => 0.,
         }
-        total_score.x / self.show_results().len() as f32
-            + total_score.x / self.show_results().len() as f32
+    }
+}
+
+impl TrialScore for f32 {
+    fn score(&self) -> f32 {
+        *self
+    }
+}
+
+/// An offset of this many points or more scores 0.
+const OFFSET_TOLERANCE: f32 = 50.;
+
+/// Offsets score 1 when spot on, down to 0 at `OFFSET_TOLERANCE` points away.
+impl TrialScore for Vec2 {
+    fn score(&self) -> f32 {
+        1. - (self.length() / OFFSET_TOLERANCE).min(1.)
+    }
+}
+
+impl TrialScore for (usize, bool) {
+    fn score(&self) -> f32 {
+        self.1.score()
+    }
+}
+
+impl<T: TrialScore> Evaluation<T> {
+    /// The score of each result, in order.
+    pub fn scores(&self) -> Vec<f32> {
+        self.show_results().iter().map(|r| r.score()).collect()
+    }
+
+    pub fn score_median(&self) -> Option<f32> {
+        stats::median(&self.scores())
+    }
+
+    pub fn score_std_dev(&self) -> Option<f32> {
+        stats::std_dev(&self.scores())
+    }
+
+    /// The score below which the given percentage (0 - 100) of scores fall.
+    pub fn score_percentile(&self, percentage: f32) -> Option<f32> {
+        stats::percentile(&self.scores(), percentage)
+    }
+
+    /// Change in score per trial over the session. Positive when the user improved.
+    pub fn learning_slope(&self) -> Option<f32> {
+        stats::slope(&self.scores())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::shared::evaluation::Evaluation;
+    use std::sync::Arc;
+
+    use crate::shared::{evaluation::Evaluation, ManualClock};
     use chrono::Duration;
+    use egui::Vec2;
 
     #[test]
     fn average_score_f32() {
@@ -246,6 +573,45 @@ mod tests {
         assert_eq!(score, 0.59999996);
     }
 
+    #[test]
+    fn average_score_vec2() {
+        let mut evaluation: Evaluation<Vec2> =
+            Evaluation::new(Duration::try_seconds(10).unwrap(), 10);
+        evaluation.add_result(Vec2::new(15., -20.));
+        evaluation.add_result(Vec2::new(15., 0.));
+        assert_eq!(evaluation.bias(), Vec2::new(15., -10.));
+        assert_eq!(evaluation.errors(), vec![25., 15.]);
+        assert_eq!(evaluation.mean_error(), 20.);
+        assert!((evaluation.average_score() - 0.6).abs() < 1e-6);
+
+        // Opposite offsets have no bias, but are still off.
+        let mut evaluation: Evaluation<Vec2> =
+            Evaluation::new(Duration::try_seconds(10).unwrap(), 10);
+        evaluation.add_result(Vec2::new(15., -20.));
+        evaluation.add_result(Vec2::new(-15., 20.));
+        assert_eq!(evaluation.bias(), Vec2::ZERO);
+        assert_eq!(evaluation.average_score(), 0.5);
+
+        // Misses beyond the tolerance score 0, not less.
+        evaluation.add_result(Vec2::new(300., 400.));
+        assert_eq!(evaluation.scores(), vec![0.5, 0.5, 0.]);
+    }
+
+    #[test]
+    fn score_statistics() {
+        let mut evaluation: Evaluation<bool> =
+            Evaluation::new(Duration::try_seconds(10).unwrap(), 10);
+        assert_eq!(evaluation.score_median(), None);
+        for result in [false, false, true, true] {
+            evaluation.add_result(result);
+        }
+        assert_eq!(evaluation.scores(), vec![0., 0., 1., 1.]);
+        assert_eq!(evaluation.score_median(), Some(0.5));
+        assert_eq!(evaluation.score_std_dev(), Some(0.5));
+        assert_eq!(evaluation.score_percentile(100.), Some(1.));
+        assert_eq!(evaluation.learning_slope(), Some(0.4));
+    }
+
     #[test]
     fn average_score_bool() {
         let mut evaluation: Evaluation<bool> =
@@ -256,4 +622,127 @@ mod tests {
         let score = evaluation.average_score();
         assert_eq!(score, 0.33333334);
     }
+
+    #[test]
+    fn take_record_once() {
+        let mut evaluation: Evaluation<bool> =
+            Evaluation::new(Duration::try_seconds(10).unwrap(), 2);
+        evaluation.start();
+        evaluation.add_result(true);
+        assert!(evaluation.take_record("Test", "Default", 1.0).is_none());
+        evaluation.add_result(false);
+        let record = evaluation.take_record("Test", "Default", 0.5).unwrap();
+        assert_eq!(record.reps_done, 2);
+        assert_eq!(
+            record.results,
+            vec![serde_json::json!(true), serde_json::json!(false)]
+        );
+        assert!(evaluation.take_record("Test", "Default", 0.5).is_none());
+    }
+
+    #[test]
+    fn reaction_times() {
+        let mut evaluation: Evaluation<bool> =
+            Evaluation::new(Duration::try_seconds(10).unwrap(), 10);
+        assert_eq!(evaluation.reaction_time_mean(), None);
+
+        // A result without stimulus onset or response has no reaction time.
+        evaluation.add_result(true);
+        evaluation.set_stimulus_onset();
+        evaluation.add_missed_result(false);
+        assert!(evaluation.reaction_times_ms().is_empty());
+        assert_eq!(evaluation.show_timings().len(), 2);
+
+        // Set timings by hand so we don't have to sleep.
+        let onset = chrono::Local::now();
+        for ms in [100, 300, 200, 400] {
+            evaluation.set_stimulus_onset();
+            evaluation.add_result(true);
+            let timing = evaluation.timings.last_mut().unwrap();
+            timing.onset = Some(onset);
+            timing.response = Some(onset + Duration::try_milliseconds(ms).unwrap());
+        }
+        assert_eq!(evaluation.reaction_times_ms(), vec![100., 300., 200., 400.]);
+        assert_eq!(evaluation.reaction_time_mean(), Some(250.));
+        assert_eq!(evaluation.reaction_time_median(), Some(250.));
+        assert_eq!(evaluation.reaction_time_variance(), Some(12500.));
+    }
+
+    #[test]
+    fn replay_seed() {
+        use rand::Rng;
+
+        let mut evaluation: Evaluation<bool> =
+            Evaluation::new(Duration::try_seconds(10).unwrap(), 10);
+        evaluation.start();
+        let seed = evaluation.seed();
+        let first: Vec<u32> = (0..5).map(|_| evaluation.rng().gen()).collect();
+
+        // Replaying the seed gives the same stimuli.
+        evaluation.replay_seed = Some(seed);
+        evaluation.start();
+        let second: Vec<u32> = (0..5).map(|_| evaluation.rng().gen()).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn finish_on_time() {
+        let clock = ManualClock::new();
+        let mut evaluation: Evaluation<bool> = Evaluation::with_clock(
+            Duration::try_seconds(10).unwrap(),
+            10,
+            Arc::new(clock.clone()),
+        );
+        evaluation.start();
+        evaluation.add_result(true);
+        clock.advance(Duration::try_seconds(5).unwrap());
+        assert!(!evaluation.is_finished());
+        assert_eq!(
+            evaluation.time_remaining(),
+            Duration::try_seconds(5).unwrap()
+        );
+
+        // Time spent paused doesn't count.
+        evaluation.pause();
+        clock.advance(Duration::try_seconds(60).unwrap());
+        assert!(!evaluation.is_finished());
+        evaluation.resume();
+
+        clock.advance(Duration::try_seconds(6).unwrap());
+        assert!(evaluation.is_finished());
+    }
+
+    #[test]
+    fn reaction_times_follow_the_clock() {
+        let clock = ManualClock::new();
+        let mut evaluation: Evaluation<bool> = Evaluation::with_clock(
+            Duration::try_seconds(10).unwrap(),
+            10,
+            Arc::new(clock.clone()),
+        );
+        evaluation.start();
+        evaluation.set_stimulus_onset();
+        clock.advance(Duration::try_milliseconds(250).unwrap());
+        evaluation.add_result(true);
+
+        // Time spent paused doesn't count towards the reaction time.
+        evaluation.set_stimulus_onset();
+        clock.advance(Duration::try_milliseconds(100).unwrap());
+        evaluation.pause();
+        clock.advance(Duration::try_seconds(30).unwrap());
+        evaluation.resume();
+        clock.advance(Duration::try_milliseconds(200).unwrap());
+        evaluation.add_result(true);
+
+        assert_eq!(evaluation.reaction_times_ms(), vec![250., 300.]);
+
+        // The start time stays when the session really started, the pause is
+        // kept apart and subtracted from the time taken.
+        let start_time = evaluation.start_time;
+        evaluation.set_reps(2);
+        let record = evaluation.take_record("Test", "Default", 1.).unwrap();
+        assert_eq!(record.start_time, start_time);
+        assert_eq!(record.paused_ms, 30_000);
+        assert_eq!(evaluation.time_taken(), Duration::try_milliseconds(550));
+    }
 }
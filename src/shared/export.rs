@@ -0,0 +1,337 @@
+//! Export the raw trial data of the training history for research, as tidy CSV
+//! or JSON with one row per trial. Also saves exported files and reports.
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::history::SessionRecord;
+
+/// A single trial of a session, with everything needed to analyse it on its own.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TrialRow {
+    /// The profile that did the session.
+    pub participant: String,
+    /// Sessions are numbered in the order they were added to the history.
+    pub session_id: usize,
+    pub session_start: DateTime<Local>,
+    pub exercise: String,
+    pub config: String,
+    /// Trials are numbered from 1 within a session.
+    pub trial: usize,
+    pub stimulus: Value,
+    pub response: Value,
+    /// The result as recorded by the exercise, e.g. a score or an offset.
+    pub result: Value,
+    /// Whether the response was correct. None if the result doesn't say.
+    pub correct: Option<bool>,
+    pub reaction_time_ms: Option<i64>,
+}
+
+/// Determine whether a result means a correct response.
+fn correct(result: &Value) -> Option<bool> {
+    match result {
+        Value::Bool(b) => Some(*b),
+        Value::Number(n) => Some(n.as_f64()? >= 1.),
+        Value::Array(values) => values.iter().find_map(|v| v.as_bool()),
+        _ => None,
+    }
+}
+
+/// One row per trial for all given records. Records should be in history order,
+/// so the session ids stay the same between exports.
+pub fn trial_rows(records: &[SessionRecord]) -> Vec<TrialRow> {
+    let mut rows = vec![];
+    for (session, record) in records.iter().enumerate() {
+        for (i, result) in record.results.iter().enumerate() {
+            let details = record.details.get(i).cloned().unwrap_or_default();
+            rows.push(TrialRow {
+                participant: record.profile.to_owned(),
+                session_id: session + 1,
+                session_start: record.start_time,
+                exercise: record.exercise.to_owned(),
+                config: record.config.to_owned(),
+                trial: i + 1,
+                stimulus: details.stimulus,
+                response: details.response,
+                result: result.to_owned(),
+                correct: correct(result),
+                reaction_time_ms: record
+                    .timings
+                    .get(i)
+                    .and_then(|timing| timing.reaction_time())
+                    .map(|rt| rt.num_milliseconds()),
+            });
+        }
+    }
+    rows
+}
+
+/// Flatten a json value into named columns: the fields of objects become
+/// columns of their own, e.g. `position` becomes `position_x` and `position_y`.
+fn flatten(name: &str, value: &Value, columns: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let name = match name.is_empty() {
+                    true => key.to_owned(),
+                    false => format!("{}_{}", name, key),
+                };
+                flatten(&name, value, columns);
+            }
+        }
+        Value::Null if name.is_empty() => (),
+        _ => columns.push((name.to_owned(), value.to_owned())),
+    }
+}
+
+/// The text of a single csv cell. Lists and objects are written as json.
+fn cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.to_owned(),
+        _ => value.to_string(),
+    };
+    match text.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text,
+    }
+}
+
+/// The flattened values of each row, and the names of all their columns in order.
+fn flattened_columns(
+    rows: &[TrialRow],
+    name: &str,
+    value: impl Fn(&TrialRow) -> &Value,
+) -> (Vec<Vec<(String, Value)>>, Vec<String>) {
+    let values: Vec<Vec<(String, Value)>> = rows
+        .iter()
+        .map(|row| {
+            let mut columns = vec![];
+            flatten(name, value(row), &mut columns);
+            columns
+        })
+        .collect();
+    let mut names: Vec<String> = vec![];
+    for (name, _) in values.iter().flatten() {
+        if !names.contains(name) {
+            names.push(name.to_owned());
+        }
+    }
+    (values, names)
+}
+
+/// The cells of a row for the given columns. Missing columns are left empty.
+fn cells_for(values: &[(String, Value)], columns: &[String]) -> Vec<String> {
+    columns
+        .iter()
+        .map(
+            |column| match values.iter().find(|(name, _)| name == column) {
+                Some((_, value)) => cell(value),
+                None => String::new(),
+            },
+        )
+        .collect()
+}
+
+/// All trials as csv. Every stimulus parameter, e.g. `background_offset`,
+/// gets its own column, and so does every part of a response, e.g.
+/// `response_x`. Trials without that parameter leave it empty.
+pub fn to_csv(rows: &[TrialRow]) -> String {
+    let (stimuli, stimulus_columns) = flattened_columns(rows, "", |row| &row.stimulus);
+    let (responses, response_columns) = flattened_columns(rows, "response", |row| &row.response);
+
+    let mut headers = vec![
+        "participant",
+        "session_id",
+        "session_start",
+        "exercise",
+        "config",
+        "trial",
+    ];
+    headers.extend(stimulus_columns.iter().map(|c| c.as_str()));
+    headers.extend(response_columns.iter().map(|c| c.as_str()));
+    headers.extend(["result", "correct", "reaction_time_ms"]);
+    let mut csv = headers
+        .iter()
+        .map(|h| cell(&Value::from(*h)))
+        .collect::<Vec<String>>()
+        .join(",");
+    csv += "\n";
+
+    for ((row, stimulus), response) in rows.iter().zip(&stimuli).zip(&responses) {
+        let mut cells = vec![
+            cell(&Value::from(row.participant.as_str())),
+            row.session_id.to_string(),
+            row.session_start.to_rfc3339(),
+            cell(&Value::from(row.exercise.as_str())),
+            cell(&Value::from(row.config.as_str())),
+            row.trial.to_string(),
+        ];
+        cells.extend(cells_for(stimulus, &stimulus_columns));
+        cells.extend(cells_for(response, &response_columns));
+        cells.push(cell(&row.result));
+        cells.push(row.correct.map(|c| c.to_string()).unwrap_or_default());
+        cells.push(
+            row.reaction_time_ms
+                .map(|rt| rt.to_string())
+                .unwrap_or_default(),
+        );
+        csv += &cells.join(",");
+        csv += "\n";
+    }
+    csv
+}
+
+/// All trials as a json list of objects, with the same fields as the csv.
+pub fn to_json(rows: &[TrialRow]) -> Result<String, String> {
+    serde_json::to_string_pretty(rows).map_err(|e| e.to_string())
+}
+
+/// Save an exported file. On native, the file is written to the exports folder.
+/// Returns where the file was saved.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(filename: &str, contents: String) -> Result<String, String> {
+    use super::asset_loader::write_string_to_file;
+    use std::path::Path;
+
+    let dir = Path::new("./exports");
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to save {}: {}", filename, e))?;
+    let path = dir.join(filename);
+    write_string_to_file(&path, contents)
+        .map_err(|e| format!("Failed to save {}: {}", filename, e))?;
+    Ok(path.display().to_string())
+}
+
+/// Save an exported file. On the web, the file is offered as a download.
+/// Returns the name of the download.
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(filename: &str, contents: String) -> Result<String, String> {
+    use wasm_bindgen::JsCast;
+
+    let mime_type = match filename.rsplit('.').next() {
+        Some("html") => "text/html",
+        Some("md") => "text/markdown",
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        _ => "text/plain",
+    };
+    let anchor = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("a").ok())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+        .ok_or(format!("Failed to offer {} as a download.", filename))?;
+    anchor.set_href(&format!(
+        "data:{};charset=utf-8,{}",
+        mime_type,
+        percent_encode(&contents)
+    ));
+    anchor.set_download(filename);
+    anchor.click();
+    Ok(String::from(filename))
+}
+
+/// Percent-encode text for use in a data url.
+#[cfg(target_arch = "wasm32")]
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded += &format!("%{:02X}", byte),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{TrialDetails, TrialTiming};
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn record(exercise: &str, results: Vec<Value>, details: Vec<TrialDetails>) -> SessionRecord {
+        let start_time = Local::now();
+        SessionRecord {
+            profile: String::from("P01"),
+            exercise: String::from(exercise),
+            config: String::from("Easy, slow"),
+            start_time,
+            end_time: start_time + Duration::try_seconds(60).unwrap(),
+            paused_ms: 0,
+            repetitions: 10,
+            reps_done: results.len(),
+            results,
+            timings: vec![TrialTiming {
+                onset: Some(start_time),
+                response: Some(start_time + Duration::try_milliseconds(450).unwrap()),
+            }],
+            details,
+            seed: None,
+            score: 0.5,
+        }
+    }
+
+    #[test]
+    fn tidy_csv() {
+        let records = vec![
+            record(
+                "Vergence",
+                vec![json!(true), json!(false)],
+                vec![TrialDetails {
+                    stimulus: json!({ "focal_position": "Up", "background_offset": 3 }),
+                    response: json!("Down"),
+                }],
+            ),
+            record(
+                "Recognition",
+                vec![json!(0.5)],
+                vec![TrialDetails {
+                    stimulus: Value::Null,
+                    response: json!({ "x": 2, "y": -1 }),
+                }],
+            ),
+        ];
+        let rows = trial_rows(&records);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1].trial, 2);
+        assert_eq!(rows[2].session_id, 2);
+        assert_eq!(rows[2].correct, Some(false));
+
+        let csv = to_csv(&rows);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "participant,session_id,session_start,exercise,config,trial,\
+            background_offset,focal_position,response,response_x,response_y,\
+            result,correct,reaction_time_ms"
+        );
+        assert!(lines[1].starts_with("P01,1,"));
+        assert!(lines[1].ends_with(",Vergence,\"Easy, slow\",1,3,Up,Down,,,true,true,450"));
+        assert!(lines[2].ends_with(",Vergence,\"Easy, slow\",2,,,,,,false,false,"));
+        assert!(lines[3].ends_with(",Recognition,\"Easy, slow\",1,,,,2,-1,0.5,false,450"));
+    }
+
+    #[test]
+    fn flatten_stimulus() {
+        let mut columns = vec![];
+        flatten(
+            "",
+            &json!({ "position": { "x": 0.5, "y": 0.25 }, "sequence": [1, 2] }),
+            &mut columns,
+        );
+        assert_eq!(
+            columns,
+            vec![
+                (String::from("position_x"), json!(0.5)),
+                (String::from("position_y"), json!(0.25)),
+                (String::from("sequence"), json!([1, 2])),
+            ]
+        );
+        assert_eq!(cell(&json!([1, 2])), "\"[1,2]\"");
+    }
+}
@@ -0,0 +1,262 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    evaluation::{TrialDetails, TrialTiming},
+    profile::DEFAULT_PROFILE,
+    storage,
+};
+
+/// The storage key for the history.
+const HISTORY_KEY: &str = "history";
+/// The storage key for a copy of a stored history that couldn't be read.
+const HISTORY_BACKUP_KEY: &str = "history_bak";
+
+/// A record of a single finished evaluation.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SessionRecord {
+    /// The profile this session belongs to. Records from older versions belong to the default profile.
+    #[serde(default = "default_profile")]
+    pub profile: String,
+    pub exercise: String,
+    pub config: String,
+    pub start_time: DateTime<Local>,
+    pub end_time: DateTime<Local>,
+    /// Time spent paused between the start and the end. Records from older versions
+    /// couldn't be paused.
+    #[serde(default)]
+    pub paused_ms: i64,
+    pub repetitions: usize,
+    pub reps_done: usize,
+    pub results: Vec<serde_json::Value>,
+    /// Timing of each result. Records from older versions don't have timings.
+    #[serde(default)]
+    pub timings: Vec<TrialTiming>,
+    /// Stimulus and response of each result. Records from older versions don't have details.
+    #[serde(default)]
+    pub details: Vec<TrialDetails>,
+    /// The seed for the stimuli of the session. Records from older versions don't have a seed.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub score: f32,
+}
+
+fn default_profile() -> String {
+    String::from(DEFAULT_PROFILE)
+}
+
+impl SessionRecord {
+    /// Time taken for the session, without the time spent paused.
+    pub fn duration(&self) -> Duration {
+        self.end_time
+            - self.start_time
+            - Duration::try_milliseconds(self.paused_ms).unwrap_or_default()
+    }
+
+    /// Average time taken per rep in seconds. Returns 0 if no reps were done.
+    pub fn secs_per_rep(&self) -> f32 {
+        if self.reps_done == 0 {
+            return 0.;
+        }
+        self.duration().num_milliseconds() as f32 / 1000. / self.reps_done as f32
+    }
+}
+
+/// The training history: all finished evaluations, oldest first.
+/// The history holds the records of all profiles, but queries only return
+/// the records of the active profile.
+#[derive(Debug)]
+pub struct History {
+    records: Vec<SessionRecord>,
+    profile: String,
+    /// False when the stored history couldn't be read or backed up, so saving
+    /// would overwrite it.
+    can_save: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            records: vec![],
+            profile: default_profile(),
+            can_save: true,
+        }
+    }
+}
+
+impl History {
+    /// Load the history from storage. Returns an empty history if nothing is stored yet.
+    /// If the stored history can't be read, it is backed up and an empty history is
+    /// returned with the error. If the backup fails too, the empty history won't be
+    /// saved, so the stored history is never overwritten.
+    pub fn load() -> (Self, Option<String>) {
+        let Some(json) = storage::read(HISTORY_KEY) else {
+            debug!("History: no stored history found, starting fresh.");
+            return (Self::default(), None);
+        };
+        match serde_json::from_str(&json) {
+            Ok(records) => (
+                Self {
+                    records,
+                    ..Default::default()
+                },
+                None,
+            ),
+            Err(e) => {
+                warn!("History: failed to deserialize history: {}", e);
+                match storage::write(HISTORY_BACKUP_KEY, json) {
+                    Ok(()) => (
+                        Self::default(),
+                        Some(format!(
+                            "The training history couldn't be read ({}). It was backed up as {} and a new history was started.",
+                            e, HISTORY_BACKUP_KEY
+                        )),
+                    ),
+                    Err(backup_error) => (
+                        Self {
+                            can_save: false,
+                            ..Default::default()
+                        },
+                        Some(format!(
+                            "The training history couldn't be read ({}) or backed up ({}). New sessions won't be saved.",
+                            e, backup_error
+                        )),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Add a record for the active profile and write the history to storage.
+    pub fn add(&mut self, mut record: SessionRecord) -> Result<(), String> {
+        record.profile = self.profile.clone();
+        self.records.push(record);
+        self.save()
+    }
+
+    /// Write the history to storage.
+    pub fn save(&self) -> Result<(), String> {
+        if !self.can_save {
+            return Err(String::from(
+                "Not saved, the stored history couldn't be read or backed up.",
+            ));
+        }
+        let json = serde_json::to_string(&self.records).map_err(|e| e.to_string())?;
+        storage::write(HISTORY_KEY, json)
+    }
+
+    /// Set the profile whose records are returned by the queries.
+    pub fn set_profile(&mut self, profile: &str) {
+        self.profile = String::from(profile);
+    }
+
+    // ***********
+    // Queries
+    // ***********
+
+    /// All records of the active profile, oldest first.
+    pub fn records(&self) -> Vec<&SessionRecord> {
+        self.records
+            .iter()
+            .filter(|r| r.profile == self.profile)
+            .collect()
+    }
+
+    /// All records of all profiles, oldest first.
+    pub fn all_records(&self) -> &[SessionRecord] {
+        &self.records
+    }
+
+    /// All records for a given exercise, oldest first.
+    pub fn for_exercise(&self, exercise: &str) -> Vec<&SessionRecord> {
+        self.records()
+            .into_iter()
+            .filter(|r| r.exercise == exercise)
+            .collect()
+    }
+
+    /// All records that started within the given range (inclusive), oldest first.
+    pub fn between(&self, from: DateTime<Local>, to: DateTime<Local>) -> Vec<&SessionRecord> {
+        self.records()
+            .into_iter()
+            .filter(|r| r.start_time >= from && r.start_time <= to)
+            .collect()
+    }
+
+    /// The names of all exercises in the history, without duplicates.
+    pub fn exercises(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for record in self.records() {
+            if !names.contains(&record.exercise.as_str()) {
+                names.push(record.exercise.as_str());
+            }
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(exercise: &str, start_time: DateTime<Local>) -> SessionRecord {
+        SessionRecord {
+            profile: default_profile(),
+            exercise: String::from(exercise),
+            config: String::from("Default"),
+            start_time,
+            end_time: start_time + Duration::try_seconds(60).unwrap(),
+            paused_ms: 0,
+            repetitions: 10,
+            reps_done: 2,
+            results: vec![serde_json::json!(true), serde_json::json!(false)],
+            timings: vec![],
+            details: vec![],
+            seed: Some(42),
+            score: 0.5,
+        }
+    }
+
+    #[test]
+    fn record_roundtrip() {
+        let rec = record("Vergence", Local::now());
+        let json = serde_json::to_string(&rec).unwrap();
+        let deser: SessionRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(rec, deser);
+        assert_eq!(rec.secs_per_rep(), 30.);
+    }
+
+    #[test]
+    fn queries() {
+        let now = Local::now();
+        let yesterday = now - Duration::try_days(1).unwrap();
+        let mut other = record("Vergence", now);
+        other.profile = String::from("Other");
+        let mut history = History {
+            records: vec![
+                record("Vergence", yesterday),
+                record("Recognition", now),
+                record("Vergence", now),
+                other,
+            ],
+            ..Default::default()
+        };
+        assert_eq!(history.for_exercise("Vergence").len(), 2);
+        assert_eq!(history.exercises(), vec!["Vergence", "Recognition"]);
+        let hour = Duration::try_hours(1).unwrap();
+        assert_eq!(history.between(now - hour, now + hour).len(), 2);
+
+        history.set_profile("Other");
+        assert_eq!(history.records().len(), 1);
+        assert_eq!(history.exercises(), vec!["Vergence"]);
+    }
+
+    #[test]
+    fn unreadable_history_is_not_overwritten() {
+        let mut history = History {
+            can_save: false,
+            ..Default::default()
+        };
+        assert!(history.add(record("Vergence", Local::now())).is_err());
+    }
+}
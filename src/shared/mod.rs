@@ -2,18 +2,26 @@ pub mod anaglyph;
 pub mod asset_loader;
 pub mod cpal_audio;
 pub mod egui_style;
+pub mod export;
+pub mod report;
+pub mod stats;
 
 mod about_screen;
 mod anaglyph_color;
 mod appdata;
 mod evaluation;
+mod history;
 mod pos3;
+mod profile;
+mod storage;
 mod timer;
 
 pub use about_screen::about_screen;
 pub use anaglyph::Anaglyph;
-pub use anaglyph_color::AnaglyphColor;
+pub use anaglyph_color::{display_id, AnaglyphColor};
 pub use appdata::AppData;
-pub use evaluation::Evaluation;
+pub use evaluation::{Evaluation, TrialDetails, TrialScore, TrialTiming};
+pub use history::{History, SessionRecord};
 pub use pos3::Pos3;
-pub use timer::Timer;
+pub use profile::{Profile, ProfileChange, Profiles, DEFAULT_PROFILE};
+pub use timer::{Clock, ManualClock, MonotonicClock, Timer};
@@ -18,8 +18,12 @@ impl Pos3 {
 
     /// Generate a random point in 3D space, given the maximum size of each dimension.
     /// Note: all input is normalized to 1.0.
-    pub fn random(x_size: usize, y_size: usize, z_size: usize) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random<R: Rng + ?Sized>(
+        rng: &mut R,
+        x_size: usize,
+        y_size: usize,
+        z_size: usize,
+    ) -> Self {
         let mut normalize = |size: usize| -> f32 {
             let random_number_in_range = rng.gen_range(0..size);
             random_number_in_range as f32 / size as f32
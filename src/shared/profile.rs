@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{storage, AnaglyphColor};
+
+/// The name of the profile that is always present.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// The storage key for the profiles.
+const PROFILES_KEY: &str = "profiles";
+
+/// A user profile. Each patient gets their own profile, which owns their
+/// settings. The training history is linked to a profile by its name.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// The most recent anaglyph calibration, used on displays without their own calibration.
+    #[serde(default)]
+    pub anaglyph_color: AnaglyphColor,
+    /// The anaglyph calibration for each display, by display id.
+    #[serde(default)]
+    pub calibrations: BTreeMap<String, AnaglyphColor>,
+    /// The preferred language, e.g. "English".
+    #[serde(default)]
+    pub language: Option<String>,
+    /// The preferred exercise config name for each exercise, by exercise name.
+    #[serde(default)]
+    pub configs: BTreeMap<String, String>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            anaglyph_color: AnaglyphColor::default(),
+            calibrations: BTreeMap::new(),
+            language: None,
+            configs: BTreeMap::new(),
+        }
+    }
+
+    /// The anaglyph calibration for a display.
+    pub fn calibration(&self, display: &str) -> &AnaglyphColor {
+        self.calibrations
+            .get(display)
+            .unwrap_or(&self.anaglyph_color)
+    }
+
+    /// The preferred config name for an exercise, if any.
+    pub fn config(&self, exercise: &str) -> Option<&str> {
+        self.configs.get(exercise).map(|c| c.as_str())
+    }
+}
+
+/// A change to the active profile. Exercises only get a reference to AppData,
+/// so they send these through `AppData::profile_tx`.
+#[derive(Debug, Clone)]
+pub enum ProfileChange {
+    AnaglyphColor {
+        display: String,
+        color: AnaglyphColor,
+    },
+    Config {
+        exercise: String,
+        config: String,
+    },
+    Language(String),
+}
+
+/// All profiles and which one is active.
+/// Stored in a local json file on native and in the browser's local storage on the web.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    active: usize,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            profiles: vec![Profile::new(DEFAULT_PROFILE)],
+            active: 0,
+        }
+    }
+}
+
+impl Profiles {
+    /// Load the profiles from storage. Returns only the default profile if nothing
+    /// is stored yet or the stored profiles can't be read.
+    pub fn load() -> Self {
+        let json = match storage::read(PROFILES_KEY) {
+            Some(json) => json,
+            None => {
+                debug!("Profiles: no stored profiles found, using default profile.");
+                return Self::default();
+            }
+        };
+        match serde_json::from_str::<Self>(&json) {
+            Ok(profiles) if profiles.profiles.get(profiles.active).is_some() => profiles,
+            Ok(_) => {
+                warn!("Profiles: stored profiles have no valid active profile.");
+                Self::default()
+            }
+            Err(e) => {
+                warn!("Profiles: failed to deserialize profiles: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the profiles to storage.
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string(&self).map_err(|e| e.to_string())?;
+        storage::write(PROFILES_KEY, json)
+    }
+
+    /// The active profile.
+    pub fn active(&self) -> &Profile {
+        &self.profiles[self.active]
+    }
+
+    /// The names of all profiles.
+    pub fn names(&self) -> Vec<&str> {
+        self.profiles.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    /// Make the profile with the given name active and write the profiles to storage.
+    pub fn select(&mut self, name: &str) -> Result<(), String> {
+        self.activate(name)?;
+        self.save()
+    }
+
+    /// Add a new profile and write the profiles to storage.
+    pub fn add(&mut self, name: &str) -> Result<(), String> {
+        self.insert(name)?;
+        self.save()
+    }
+
+    /// Remove a profile and write the profiles to storage.
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        self.delete(name)?;
+        self.save()
+    }
+
+    /// Make the profile with the given name active.
+    /// Returns an error if there is no such profile.
+    fn activate(&mut self, name: &str) -> Result<(), String> {
+        match self.profiles.iter().position(|p| p.name == name) {
+            Some(index) => {
+                self.active = index;
+                Ok(())
+            }
+            None => Err(format!("There is no profile named {}.", name)),
+        }
+    }
+
+    /// Add a new profile. Names must be unique and can't be empty.
+    fn insert(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(String::from("A profile needs a name."));
+        }
+        if self.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("A profile named {} already exists.", name));
+        }
+        self.profiles.push(Profile::new(name));
+        Ok(())
+    }
+
+    /// Remove a profile. The last remaining profile can't be removed.
+    /// When the active profile is removed, the first profile becomes active.
+    fn delete(&mut self, name: &str) -> Result<(), String> {
+        if self.profiles.len() <= 1 {
+            return Err(String::from("Can't remove the last profile."));
+        }
+        let index = match self.profiles.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => return Err(format!("There is no profile named {}.", name)),
+        };
+        self.profiles.remove(index);
+        if self.active == index {
+            self.active = 0;
+        } else if self.active > index {
+            self.active -= 1;
+        }
+        Ok(())
+    }
+
+    /// Apply a change to the active profile and write the profiles to storage.
+    pub fn apply(&mut self, change: ProfileChange) -> Result<(), String> {
+        let profile = &mut self.profiles[self.active];
+        match change {
+            ProfileChange::AnaglyphColor { display, color } => {
+                profile.anaglyph_color = color.clone();
+                profile.calibrations.insert(display, color);
+            }
+            ProfileChange::Config { exercise, config } => {
+                profile.configs.insert(exercise, config);
+            }
+            ProfileChange::Language(language) => profile.language = Some(language),
+        }
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_select_remove() {
+        let mut profiles = Profiles::default();
+        assert!(profiles.insert(" ").is_err());
+        assert!(profiles.insert(DEFAULT_PROFILE).is_err());
+        profiles.insert("Alice").unwrap();
+        profiles.insert(" Bob ").unwrap();
+        assert_eq!(profiles.names(), vec![DEFAULT_PROFILE, "Alice", "Bob"]);
+
+        // Removing a profile before the active one keeps the same profile active.
+        profiles.activate("Bob").unwrap();
+        profiles.delete("Alice").unwrap();
+        assert_eq!(profiles.active().name, "Bob");
+        assert!(profiles.activate("Alice").is_err());
+
+        // Removing the active profile activates the first one.
+        profiles.delete("Bob").unwrap();
+        assert_eq!(profiles.active().name, DEFAULT_PROFILE);
+        assert!(profiles.delete(DEFAULT_PROFILE).is_err());
+    }
+
+    #[test]
+    fn profile_roundtrip() {
+        let mut profile = Profile::new("Alice");
+        profile
+            .configs
+            .insert(String::from("Vergence"), String::from("Easy"));
+        let json = serde_json::to_string(&profile).unwrap();
+        let deser: Profile = serde_json::from_str(&json).unwrap();
+        assert_eq!(profile, deser);
+        assert_eq!(deser.config("Vergence"), Some("Easy"));
+
+        // Profiles stored without settings get the defaults.
+        let deser: Profile = serde_json::from_str(r#"{"name": "Bob"}"#).unwrap();
+        assert_eq!(deser, Profile::new("Bob"));
+    }
+
+    #[test]
+    fn calibration_per_display() {
+        let mut profiles = Profiles::default();
+        let color = AnaglyphColor {
+            checked: true,
+            ..Default::default()
+        };
+        profiles
+            .profiles
+            .first_mut()
+            .unwrap()
+            .calibrations
+            .insert(String::from("1920x1080"), color.clone());
+        assert_eq!(profiles.active().calibration("1920x1080"), &color);
+        // Other displays use the most recent calibration.
+        assert_eq!(
+            profiles.active().calibration("2560x1440"),
+            &AnaglyphColor::default()
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! Printable reports of the training history: a single session or all sessions
+//! within a date range, as a self-contained HTML or Markdown document.
+
+use chrono::{Local, NaiveDate};
+
+use super::{history::SessionRecord, stats};
+
+/// The document format of a report.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl std::fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportFormat::Html => write!(f, "HTML"),
+            ReportFormat::Markdown => write!(f, "Markdown"),
+        }
+    }
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Builds a document in the given format, one block at a time.
+struct Document {
+    format: ReportFormat,
+    body: String,
+}
+
+impl Document {
+    fn new(format: ReportFormat) -> Self {
+        Self {
+            format,
+            body: String::new(),
+        }
+    }
+
+    fn heading(&mut self, level: usize, text: &str) {
+        match self.format {
+            ReportFormat::Html => self.body += &format!("<h{0}>{1}</h{0}>\n", level, escape(text)),
+            ReportFormat::Markdown => self.body += &format!("{} {}\n\n", "#".repeat(level), text),
+        }
+    }
+
+    fn paragraph(&mut self, text: &str) {
+        match self.format {
+            ReportFormat::Html => self.body += &format!("<p>{}</p>\n", escape(text)),
+            ReportFormat::Markdown => self.body += &format!("{}\n\n", text),
+        }
+    }
+
+    fn table(&mut self, headers: &[&str], rows: &[Vec<String>]) {
+        match self.format {
+            ReportFormat::Html => {
+                self.body += "<table>\n<tr>";
+                for header in headers {
+                    self.body += &format!("<th>{}</th>", escape(header));
+                }
+                self.body += "</tr>\n";
+                for row in rows {
+                    self.body += "<tr>";
+                    for cell in row {
+                        self.body += &format!("<td>{}</td>", escape(cell));
+                    }
+                    self.body += "</tr>\n";
+                }
+                self.body += "</table>\n";
+            }
+            ReportFormat::Markdown => {
+                self.body += &format!("| {} |\n", headers.join(" | "));
+                self.body += &format!("|{}\n", " --- |".repeat(headers.len()));
+                for row in rows {
+                    let cells: Vec<String> = row
+                        .iter()
+                        .map(|cell| cell.replace('|', "\\|").replace('\n', " "))
+                        .collect();
+                    self.body += &format!("| {} |\n", cells.join(" | "));
+                }
+                self.body += "\n";
+            }
+        }
+    }
+
+    /// Finish the document. An HTML document gets a title and its own styling,
+    /// so it can be opened and printed without anything else.
+    fn finish(self, title: &str) -> String {
+        match self.format {
+            ReportFormat::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+                escape(title),
+                STYLE,
+                self.body
+            ),
+            ReportFormat::Markdown => self.body,
+        }
+    }
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #999; padding: 0.2em 0.6em; text-align: left; }
+@media print { h2 { page-break-before: always; } }
+";
+
+/// Escape text for use in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Describe a single result of a session. Results are stored as json
+/// because every exercise records its own kind of result.
+fn result_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Bool(true) => String::from("Correct"),
+        serde_json::Value::Bool(false) => String::from("Incorrect"),
+        serde_json::Value::Number(n) => match n.as_f64() {
+            Some(n) => format!("{:.2}", n),
+            None => n.to_string(),
+        },
+        serde_json::Value::Array(values) => values
+            .iter()
+            .map(result_text)
+            .collect::<Vec<String>>()
+            .join(", "),
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, result_text(value)))
+            .collect::<Vec<String>>()
+            .join(", "),
+        serde_json::Value::String(s) => s.to_owned(),
+        serde_json::Value::Null => String::from("-"),
+    }
+}
+
+/// Add the details and trial table of a session to a document.
+fn add_session(doc: &mut Document, record: &SessionRecord) {
+    doc.table(
+        &[
+            "Exercise",
+            "Config",
+            "Date",
+            "Score",
+            "Reps",
+            "Time taken",
+            "Seed",
+        ],
+        &[vec![
+            record.exercise.to_owned(),
+            record.config.to_owned(),
+            record.start_time.format("%Y-%m-%d %H:%M").to_string(),
+            format!("{:.0}%", record.score * 100.),
+            format!("{} of {}", record.reps_done, record.repetitions),
+            format!("{}s", record.duration().num_seconds()),
+            match record.seed {
+                Some(seed) => seed.to_string(),
+                None => String::from("-"),
+            },
+        ]],
+    );
+
+    let trials: Vec<Vec<String>> = record
+        .results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| {
+            let reaction_time = record
+                .timings
+                .get(i)
+                .and_then(|timing| timing.reaction_time());
+            let details = record.details.get(i);
+            vec![
+                format!("{}", i + 1),
+                details.map_or(String::from("-"), |d| result_text(&d.stimulus)),
+                details.map_or(String::from("-"), |d| result_text(&d.response)),
+                result_text(result),
+                match reaction_time {
+                    Some(rt) => format!("{} ms", rt.num_milliseconds()),
+                    None => String::from("-"),
+                },
+            ]
+        })
+        .collect();
+    match trials.is_empty() {
+        true => doc.paragraph("No trials recorded."),
+        false => doc.table(
+            &["Trial", "Stimulus", "Response", "Result", "Reaction time"],
+            &trials,
+        ),
+    }
+}
+
+/// A report of a single session.
+pub fn session_report(record: &SessionRecord, format: ReportFormat) -> String {
+    let title = format!(
+        "{} - {}",
+        record.exercise,
+        record.start_time.format("%Y-%m-%d %H:%M")
+    );
+    let mut doc = Document::new(format);
+    doc.heading(1, &title);
+    doc.paragraph(&format!("Profile: {}", record.profile));
+    add_session(&mut doc, record);
+    doc.finish(&title)
+}
+
+/// A report of all given sessions from a date range. Starts with a summary of
+/// the trend for each exercise, followed by the details of every session.
+pub fn history_report(
+    records: &[&SessionRecord],
+    from: NaiveDate,
+    to: NaiveDate,
+    format: ReportFormat,
+) -> String {
+    let title = format!("Training report {} to {}", from, to);
+    let mut doc = Document::new(format);
+    doc.heading(1, &title);
+    if let Some(record) = records.first() {
+        doc.paragraph(&format!("Profile: {}", record.profile));
+    }
+    doc.paragraph(&format!(
+        "{} sessions. Created {}.",
+        records.len(),
+        Local::now().format("%Y-%m-%d %H:%M")
+    ));
+
+    // Summarize the trend of the score for each exercise
+    let mut exercises: Vec<&str> = vec![];
+    for record in records {
+        if !exercises.contains(&record.exercise.as_str()) {
+            exercises.push(record.exercise.as_str());
+        }
+    }
+    let summary: Vec<Vec<String>> = exercises
+        .iter()
+        .map(|exercise| {
+            let scores: Vec<f32> = records
+                .iter()
+                .filter(|r| r.exercise == *exercise)
+                .map(|r| r.score * 100.)
+                .collect();
+            let percentage = |score: Option<f32>| match score {
+                Some(score) => format!("{:.0}%", score),
+                None => String::from("-"),
+            };
+            vec![
+                exercise.to_string(),
+                scores.len().to_string(),
+                percentage(scores.first().copied()),
+                percentage(scores.last().copied()),
+                percentage(stats::mean(&scores)),
+                match stats::slope(&scores) {
+                    Some(slope) => format!("{:+.1}% per session", slope),
+                    None => String::from("-"),
+                },
+            ]
+        })
+        .collect();
+    doc.heading(2, "Trend");
+    match summary.is_empty() {
+        true => doc.paragraph("No sessions in this period."),
+        false => doc.table(
+            &["Exercise", "Sessions", "First", "Last", "Mean", "Trend"],
+            &summary,
+        ),
+    }
+
+    for record in records {
+        doc.heading(
+            2,
+            &format!(
+                "{} - {}",
+                record.exercise,
+                record.start_time.format("%Y-%m-%d %H:%M")
+            ),
+        );
+        add_session(&mut doc, record);
+    }
+    doc.finish(&title)
+}
+
+/// A file name for a report, e.g. "perhabs_report_2024-01-31.html".
+pub fn report_filename(name: &str, format: ReportFormat) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    format!("perhabs_report_{}.{}", name, format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{TrialDetails, TrialTiming};
+    use chrono::Duration;
+
+    fn record(exercise: &str, score: f32) -> SessionRecord {
+        let start_time = Local::now();
+        SessionRecord {
+            profile: String::from("Default"),
+            exercise: String::from(exercise),
+            config: String::from("<Hard>"),
+            start_time,
+            end_time: start_time + Duration::try_seconds(60).unwrap(),
+            paused_ms: 0,
+            repetitions: 10,
+            reps_done: 2,
+            results: vec![serde_json::json!(true), serde_json::json!(0.5)],
+            timings: vec![TrialTiming {
+                onset: Some(start_time),
+                response: Some(start_time + Duration::try_milliseconds(450).unwrap()),
+            }],
+            details: vec![TrialDetails {
+                stimulus: serde_json::json!({ "word": "HEART" }),
+                response: serde_json::json!("EARTH"),
+            }],
+            seed: Some(42),
+            score,
+        }
+    }
+
+    #[test]
+    fn session_reports() {
+        let rec = record("Vergence", 0.5);
+
+        let html = session_report(&rec, ReportFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>&lt;Hard&gt;</td>"));
+        assert!(html.contains("<td>word: HEART</td><td>EARTH</td><td>Correct</td><td>450 ms</td>"));
+        // Trials without details or timing.
+        assert!(html.contains("<td>2</td><td>-</td><td>-</td><td>0.50</td><td>-</td>"));
+
+        let markdown = session_report(&rec, ReportFormat::Markdown);
+        assert!(markdown.starts_with("# Vergence"));
+        assert!(markdown.contains("| 1 | word: HEART | EARTH | Correct | 450 ms |"));
+        assert!(markdown.contains("| 50% | 2 of 10 |"));
+    }
+
+    #[test]
+    fn history_report_trend() {
+        let records = [
+            record("Vergence", 0.5),
+            record("Recognition", 0.9),
+            record("Vergence", 0.7),
+        ];
+        let records: Vec<&SessionRecord> = records.iter().collect();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let markdown = history_report(&records, day, day, ReportFormat::Markdown);
+        assert!(markdown.contains("| Vergence | 2 | 50% | 70% | 60% | +20.0% per session |"));
+        assert!(markdown.contains("| Recognition | 1 | 90% | 90% | 90% | - |"));
+        assert_eq!(markdown.matches("## Vergence").count(), 2);
+    }
+}
@@ -0,0 +1,166 @@
+//! Descriptive statistics over a series of values, e.g. the scores or reaction
+//! times of an evaluation. All functions return None when there's nothing to
+//! compute, e.g. for an empty series.
+
+/// The arithmetic mean.
+pub fn mean(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f32>() / values.len() as f32)
+}
+
+/// The median: the middle value, or the mean of the two middle values.
+pub fn median(values: &[f32]) -> Option<f32> {
+    percentile(values, 50.)
+}
+
+/// The population variance.
+pub fn variance(values: &[f32]) -> Option<f32> {
+    let mean = mean(values)?;
+    Some(values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32)
+}
+
+/// The population standard deviation.
+pub fn std_dev(values: &[f32]) -> Option<f32> {
+    Some(variance(values)?.sqrt())
+}
+
+/// The value below which the given percentage (0 - 100) of values fall.
+/// Interpolates linearly between the two closest values.
+pub fn percentile(values: &[f32], percentage: f32) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let rank = percentage.clamp(0., 100.) / 100. * (sorted.len() - 1) as f32;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f32;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction)
+}
+
+/// The slope of the least squares line through the values, in order.
+/// For scores this is the change in score per trial: a positive slope means
+/// the user improved during the session. Needs at least two values.
+pub fn slope(values: &[f32]) -> Option<f32> {
+    if values.len() < 2 {
+        return None;
+    }
+    let n = values.len() as f32;
+    let mean_x = (n - 1.) / 2.;
+    let mean_y = mean(values)?;
+    let (covariance, variance_x) =
+        values
+            .iter()
+            .enumerate()
+            .fold((0., 0.), |(covariance, variance_x), (x, y)| {
+                let dx = x as f32 - mean_x;
+                (covariance + dx * (y - mean_y), variance_x + dx * dx)
+            });
+    Some(covariance / variance_x)
+}
+
+/// The z-score below which the given proportion (0 - 1) of a standard normal
+/// distribution falls, i.e. the inverse of its cumulative distribution. Uses
+/// Acklam's rational approximation. Returns None outside (0, 1).
+pub fn probit(p: f32) -> Option<f32> {
+    if !(p > 0. && p < 1.) {
+        return None;
+    }
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    // The tails and the central region each have their own approximation.
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+    let p = p as f64;
+    let z = if p < P_LOW {
+        tail((-2. * p.ln()).sqrt())
+    } else if p <= 1. - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    } else {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    };
+    Some(z as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descriptives() {
+        let values = [2., 4., 4., 4., 5., 5., 7., 9.];
+        assert_eq!(mean(&values), Some(5.));
+        assert_eq!(median(&values), Some(4.5));
+        assert_eq!(variance(&values), Some(4.));
+        assert_eq!(std_dev(&values), Some(2.));
+        assert_eq!(mean(&[]), None);
+        assert_eq!(std_dev(&[]), None);
+    }
+
+    #[test]
+    fn percentiles() {
+        let values = [15., 20., 35., 40., 50.];
+        assert_eq!(percentile(&values, 0.), Some(15.));
+        assert_eq!(percentile(&values, 25.), Some(20.));
+        assert_eq!(percentile(&values, 50.), Some(35.));
+        assert_eq!(percentile(&values, 90.), Some(46.));
+        assert_eq!(percentile(&values, 100.), Some(50.));
+        assert_eq!(median(&[3., 1., 2.]), Some(2.));
+    }
+
+    #[test]
+    fn learning_slope() {
+        assert_eq!(slope(&[0., 0.5, 1.]), Some(0.5));
+        assert_eq!(slope(&[1., 1., 1., 1.]), Some(0.));
+        assert_eq!(slope(&[1., 0.]), Some(-1.));
+        assert_eq!(slope(&[1.]), None);
+    }
+
+    #[test]
+    fn inverse_normal() {
+        assert_eq!(probit(0.5), Some(0.));
+        assert!((probit(0.975).unwrap() - 1.96).abs() < 0.001);
+        assert!((probit(0.025).unwrap() + 1.96).abs() < 0.001);
+        assert!((probit(0.001).unwrap() + 3.09).abs() < 0.001);
+        assert_eq!(probit(0.), None);
+        assert_eq!(probit(1.), None);
+    }
+}
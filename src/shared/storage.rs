@@ -0,0 +1,56 @@
+//! Small key/value storage for user data such as the training history and profiles.
+//! On native, each key is a json file in the appdata folder. On the web, each key
+//! is an entry in the browser's local storage.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, path::Path};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::asset_loader::write_string_to_file;
+
+/// Where a key is stored on disk (native).
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> String {
+    format!("./appdata/{}.json", key)
+}
+
+/// Read the string stored under a key. Returns None if nothing is stored.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(key: &str) -> Option<String> {
+    fs::read_to_string(path(key)).ok()
+}
+
+/// Store a string under a key, replacing whatever was stored before.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(key: &str, contents: String) -> Result<(), String> {
+    write_string_to_file(Path::new(&path(key)), contents)
+        .map_err(|e| format!("Failed to save {}: {}", key, e))
+}
+
+/// Where a key is stored in the browser (web).
+#[cfg(target_arch = "wasm32")]
+fn storage_key(key: &str) -> String {
+    format!("perhabs_{}", key)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Read the string stored under a key. Returns None if nothing is stored.
+#[cfg(target_arch = "wasm32")]
+pub fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(&storage_key(key)).ok()?
+}
+
+/// Store a string under a key, replacing whatever was stored before.
+#[cfg(target_arch = "wasm32")]
+pub fn write(key: &str, contents: String) -> Result<(), String> {
+    match local_storage() {
+        Some(storage) => storage
+            .set_item(&storage_key(key), &contents)
+            .map_err(|_| format!("Failed to save {} to browser storage.", key)),
+        None => Err(format!("No browser storage available to save {}.", key)),
+    }
+}
@@ -1,19 +1,74 @@
-use chrono::{Duration, Local, NaiveTime};
+use std::sync::{
+    atomic::{AtomicI64, Ordering},
+    Arc, OnceLock,
+};
 
-/// A simple timer based on local time differences.
+use chrono::Duration;
+use web_time::Instant;
+
+/// A source of monotonic time. Timers measure time with a clock, so tests can
+/// use a `ManualClock` instead of waiting for real time to pass.
+pub trait Clock: Send + Sync {
+    /// Time passed since an arbitrary, fixed starting point.
+    fn now(&self) -> Duration;
+}
+
+/// The default clock. Monotonic, so it doesn't jump at midnight or when the
+/// system time changes. Works on the web too.
+pub struct MonotonicClock;
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        let elapsed = ORIGIN.get_or_init(Instant::now).elapsed();
+        Duration::from_std(elapsed).unwrap_or_default()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    millis: Arc<AtomicI64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        self.millis
+            .fetch_add(duration.num_milliseconds(), Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::try_milliseconds(self.millis.load(Ordering::SeqCst)).unwrap_or_default()
+    }
+}
+
+/// A simple countdown timer that can be paused.
 pub struct Timer {
-    start_time: NaiveTime,
-    end_time: Option<NaiveTime>,
+    clock: Arc<dyn Clock>,
+    start_time: Duration,
     duration: Duration,
+    running: bool,
+    /// When the timer was paused, if it is paused.
+    paused_at: Option<Duration>,
+    /// Total time spent paused since the timer was set.
+    paused_total: Duration,
 }
 
 impl std::fmt::Debug for Timer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = format!(
-            "Start time: {}\nEnd time: {}\nDuration:{}",
-            self.start_time.to_string(),
-            self.end_time.unwrap_or_default().to_string(),
-            self.duration.to_string()
+            "Running: {}\nPaused: {}\nPassed: {}\nDuration:{}",
+            self.running,
+            self.is_paused(),
+            self.time_passed(),
+            self.duration
         );
         write!(f, "{}", s)
     }
@@ -22,55 +77,80 @@ impl std::fmt::Debug for Timer {
 impl Timer {
     /// Create a timer. Needs to be set to be used.
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(MonotonicClock))
+    }
+
+    /// Create a timer that uses the given clock.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
         Self {
-            start_time: Local::now().time(),
-            end_time: None,
+            start_time: clock.now(),
+            clock,
             duration: Duration::zero(),
+            running: false,
+            paused_at: None,
+            paused_total: Duration::zero(),
         }
     }
 
     /// Set a timer for a given duration, starting now.
     pub fn set(&mut self, duration: Duration) {
-        let now = Local::now().time();
-        self.start_time = now;
-        self.end_time = Some(now + duration);
+        self.start_time = self.clock.now();
         self.duration = duration;
+        self.running = true;
+        self.paused_at = None;
+        self.paused_total = Duration::zero();
     }
 
-    /// Stop a running timer and set `end_time` to `None`
+    /// Stop a running timer.
     pub fn reset(&mut self) {
-        self.end_time = None;
+        self.running = false;
+        self.paused_at = None;
+    }
+
+    /// Pause a running timer. Time spent paused doesn't count.
+    pub fn pause(&mut self) {
+        if self.running && self.paused_at.is_none() {
+            self.paused_at = Some(self.clock.now());
+        }
+    }
+
+    /// Resume a paused timer.
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += self.clock.now() - paused_at;
+        }
+    }
+
+    /// Returns true when the timer is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Time the timer has been running, not counting pauses.
+    fn elapsed(&self) -> Duration {
+        let now = self.paused_at.unwrap_or_else(|| self.clock.now());
+        now - self.start_time - self.paused_total
     }
 
     /// Returns true when timer is finished.
     pub fn is_finished(&self) -> bool {
-        let now = Local::now().time();
-        match self.end_time {
-            Some(end_time) => {
-                if end_time < now {
-                    return true;
-                } else {
-                    return false;
-                }
-            }
-            None => true,
+        match self.running {
+            true => self.elapsed() > self.duration,
+            false => true,
         }
     }
 
     /// Returns true when timer is running.
     pub fn is_running(&self) -> bool {
-        match self.end_time {
-            Some(_) => true,
-            None => false,
-        }
+        self.running
     }
 
     /// Return the time remaining until the timer is finished.
     pub fn remaining(&self) -> Duration {
-        if let Some(end_time) = self.end_time {
-            let now = Local::now().time();
-            if end_time > now {
-                return end_time - now;
+        if self.running {
+            let remaining = self.duration - self.elapsed();
+            if remaining > Duration::zero() {
+                return remaining;
             }
         }
         // return zero by default
@@ -83,8 +163,36 @@ impl Timer {
         if self.is_finished() {
             self.duration
         } else {
-            let now = Local::now().time();
-            now - self.start_time
+            self.elapsed()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countdown_with_pause() {
+        let clock = ManualClock::new();
+        let mut timer = Timer::with_clock(Arc::new(clock.clone()));
+        assert!(timer.is_finished());
+
+        timer.set(Duration::try_seconds(10).unwrap());
+        clock.advance(Duration::try_seconds(4).unwrap());
+        assert_eq!(timer.remaining(), Duration::try_seconds(6).unwrap());
+
+        // Time spent paused doesn't count.
+        timer.pause();
+        clock.advance(Duration::try_seconds(60).unwrap());
+        assert!(!timer.is_finished());
+        assert_eq!(timer.remaining(), Duration::try_seconds(6).unwrap());
+        timer.resume();
+
+        clock.advance(Duration::try_seconds(5).unwrap());
+        assert_eq!(timer.time_passed(), Duration::try_seconds(9).unwrap());
+        clock.advance(Duration::try_seconds(2).unwrap());
+        assert!(timer.is_finished());
+        assert_eq!(timer.remaining(), Duration::zero());
+    }
+}
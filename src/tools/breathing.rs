@@ -0,0 +1,251 @@
+use std::sync::mpsc::{self, Sender};
+
+use crate::{
+    shared::{
+        cpal_audio::{beep, AudioHandle, Voice},
+        AppData, Timer,
+    },
+    wm::{AppWin, View},
+};
+use chrono::Duration;
+use eframe::epaint::{self, CircleShape};
+use egui::{emath, pos2, vec2, Color32, Frame, Rect, Stroke, TextStyle, WidgetText};
+use tts::Tts;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Inhale,
+    HoldIn,
+    Exhale,
+    HoldOut,
+}
+
+impl std::fmt::Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Inhale => write!(f, "Breathe in"),
+            Phase::HoldIn => write!(f, "Hold"),
+            Phase::Exhale => write!(f, "Breathe out"),
+            Phase::HoldOut => write!(f, "Hold"),
+        }
+    }
+}
+
+impl Phase {
+    fn next(&self) -> Self {
+        match self {
+            Phase::Inhale => Phase::HoldIn,
+            Phase::HoldIn => Phase::Exhale,
+            Phase::Exhale => Phase::HoldOut,
+            Phase::HoldOut => Phase::Inhale,
+        }
+    }
+
+    /// Size of the circle from 0 (lungs empty) to 1 (lungs full), given the
+    /// progress through the phase.
+    fn breath(&self, progress: f32) -> f32 {
+        // Ease in and out, like a breath.
+        let eased = (1. - (progress.clamp(0., 1.) * std::f32::consts::PI).cos()) / 2.;
+        match self {
+            Phase::Inhale => eased,
+            Phase::HoldIn => 1.,
+            Phase::Exhale => 1. - eased,
+            Phase::HoldOut => 0.,
+        }
+    }
+
+    /// The pitch of the audio cue at the start of the phase.
+    fn cue_freq(&self) -> f32 {
+        match self {
+            Phase::Inhale => 392.,
+            Phase::HoldIn | Phase::HoldOut => 330.,
+            Phase::Exhale => 262.,
+        }
+    }
+}
+
+/// Paces breathing with a circle that grows when breathing in and shrinks when
+/// breathing out. Runs next to exercises, so it can be used during a session.
+pub struct Breathing {
+    running: bool,
+    inhale_secs: f32,
+    hold_in_secs: f32,
+    exhale_secs: f32,
+    hold_out_secs: f32,
+    phase: Phase,
+    phase_timer: Timer,
+    /// Play a soft tone at the start of each phase.
+    audio_cues: bool,
+    voice: Voice,
+    /// The audio stream is only opened when audio cues are first used.
+    audio: Option<(AudioHandle, Sender<Voice>)>,
+}
+
+impl Default for Breathing {
+    fn default() -> Self {
+        // A long attack and release make the tone soft.
+        let mut voice = Voice::default();
+        voice.attack = 12_000;
+        voice.sustain = 4_000;
+        voice.release = 24_000;
+        Self {
+            running: false,
+            inhale_secs: 4.,
+            hold_in_secs: 4.,
+            exhale_secs: 4.,
+            hold_out_secs: 4.,
+            phase: Phase::Inhale,
+            phase_timer: Timer::new(),
+            audio_cues: false,
+            voice,
+            audio: None,
+        }
+    }
+}
+
+impl Breathing {
+    fn phase_secs(&self, phase: Phase) -> f32 {
+        match phase {
+            Phase::Inhale => self.inhale_secs,
+            Phase::HoldIn => self.hold_in_secs,
+            Phase::Exhale => self.exhale_secs,
+            Phase::HoldOut => self.hold_out_secs,
+        }
+    }
+
+    fn start(&mut self) {
+        self.running = true;
+        self.start_phase(Phase::Inhale);
+    }
+
+    /// Start a phase, or the next one when the phase is set to 0 seconds.
+    fn start_phase(&mut self, phase: Phase) {
+        let mut phase = phase;
+        for _ in 0..4 {
+            if self.phase_secs(phase) > 0. {
+                break;
+            }
+            phase = phase.next();
+        }
+        self.phase = phase;
+        let millis = (self.phase_secs(phase) * 1000.) as i64;
+        self.phase_timer
+            .set(Duration::try_milliseconds(millis).unwrap_or_default());
+        if self.audio_cues {
+            self.cue(phase);
+        }
+    }
+
+    fn cue(&mut self, phase: Phase) {
+        let (_, voice_tx) = self.audio.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel();
+            (beep(rx), tx)
+        });
+        let mut voice = self.voice;
+        voice.freq = phase.cue_freq();
+        let _ = voice_tx.send(voice);
+    }
+
+    fn breathing_loop(&mut self) {
+        if self.phase_timer.is_finished() {
+            self.start_phase(self.phase.next());
+        }
+    }
+
+    /// Progress through the current phase, from 0 to 1.
+    fn progress(&self) -> f32 {
+        let secs = self.phase_secs(self.phase);
+        match secs > 0. {
+            true => self.phase_timer.time_passed().num_milliseconds() as f32 / 1000. / secs,
+            false => 1.,
+        }
+    }
+
+    /// Draw the pacer: a guide circle with a filled circle that breathes inside it.
+    fn paint_pacer(&self, ui: &mut egui::Ui) {
+        let color = if ui.visuals().dark_mode {
+            Color32::from_additive_luminance(196)
+        } else {
+            Color32::from_black_alpha(240)
+        };
+        let fill = ui.visuals().selection.bg_fill;
+
+        Frame::canvas(ui.style()).show(ui, |ui| {
+            let desired_size = ui.available_width() * vec2(1., 1.);
+            let (_id, rect) = ui.allocate_space(desired_size);
+
+            let to_screen =
+                emath::RectTransform::from_to(Rect::from_x_y_ranges(0.0..=1.0, -1.0..=1.0), rect);
+            let center = to_screen * pos2(0.5, 0.);
+            let max_radius = ui.available_width() * 0.45;
+            let min_radius = max_radius * 0.3;
+
+            let guide = CircleShape::stroke(center, max_radius, Stroke::new(2., color));
+            ui.painter().add(epaint::Shape::Circle(guide));
+
+            let breath = match self.running {
+                true => self.phase.breath(self.progress()),
+                false => 0.,
+            };
+            let radius = min_radius + (max_radius - min_radius) * breath;
+            ui.painter().circle_filled(center, radius, fill);
+
+            // Paint the phase and the seconds left in it
+            if self.running {
+                let seconds = self.phase_timer.remaining().num_seconds() + 1;
+                let text: WidgetText = format!("{}\n{}", self.phase, seconds).into();
+                let galley = text.into_galley(ui, None, rect.width(), TextStyle::Heading);
+                ui.painter()
+                    .galley(center - (galley.size() * 0.5), galley, color);
+            }
+        });
+    }
+}
+
+impl AppWin for Breathing {
+    fn name(&self) -> &'static str {
+        "Breathing"
+    }
+
+    // main loop
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool, appdata: &AppData, tts: &mut Tts) {
+        if self.running {
+            ctx.request_repaint();
+            self.breathing_loop();
+        }
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(250.)
+            .resizable(false)
+            .show(ctx, |ui| self.ui(ui, appdata, tts));
+    }
+}
+
+impl View for Breathing {
+    fn ui(&mut self, ui: &mut egui::Ui, _: &AppData, _: &mut Tts) {
+        self.paint_pacer(ui);
+
+        ui.vertical(|ui| {
+            ui.add(egui::Slider::new(&mut self.inhale_secs, 1.0..=10.0).text("Breathe in"));
+            ui.add(egui::Slider::new(&mut self.hold_in_secs, 0.0..=10.0).text("Hold"));
+            ui.add(egui::Slider::new(&mut self.exhale_secs, 1.0..=10.0).text("Breathe out"));
+            ui.add(egui::Slider::new(&mut self.hold_out_secs, 0.0..=10.0).text("Hold"));
+            ui.checkbox(&mut self.audio_cues, "Audio cues");
+
+            ui.horizontal(|ui| {
+                match self.running {
+                    false => {
+                        if ui.button("Start breathing").clicked() {
+                            self.start();
+                        }
+                    }
+                    true => {
+                        if ui.button("Stop breathing").clicked() {
+                            self.running = false;
+                        }
+                    }
+                };
+            });
+        });
+    }
+}
@@ -1,5 +1,6 @@
+pub mod breathing;
 pub mod clock;
 pub mod debug_info;
 pub mod metronome;
-pub mod multitasker;
+pub mod progress;
 pub mod rand_timer;
@@ -0,0 +1,260 @@
+use crate::{
+    shared::{
+        export,
+        report::{self, ReportFormat},
+        AppData, SessionRecord,
+    },
+    wm::{AppWin, View},
+};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime};
+use egui::{vec2, Color32};
+use egui_plot::{Line, Plot, PlotPoints, Points};
+use tts::Tts;
+
+/// The measures we can plot for a series of sessions.
+#[derive(PartialEq, Clone, Copy)]
+enum Measure {
+    Score,
+    Reps,
+    SecsPerRep,
+}
+
+impl std::fmt::Display for Measure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Measure::Score => write!(f, "Score (%)"),
+            Measure::Reps => write!(f, "Reps done"),
+            Measure::SecsPerRep => write!(f, "Seconds per rep"),
+        }
+    }
+}
+
+impl Measure {
+    /// Get the value of this measure from a session record.
+    fn value(&self, record: &SessionRecord) -> f64 {
+        match self {
+            Measure::Score => record.score as f64 * 100.,
+            Measure::Reps => record.reps_done as f64,
+            Measure::SecsPerRep => record.secs_per_rep() as f64,
+        }
+    }
+}
+
+/// Shows the progress over time for each exercise in the history.
+pub struct Progress {
+    selected_exercise: Option<String>,
+    report_format: ReportFormat,
+    report_from: String,
+    report_to: String,
+    report_status: String,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        let today = Local::now().date_naive();
+        Self {
+            selected_exercise: None,
+            report_format: ReportFormat::Html,
+            report_from: (today - Duration::try_days(30).unwrap_or_default()).to_string(),
+            report_to: today.to_string(),
+            report_status: String::new(),
+        }
+    }
+}
+
+/// Parse a date like "2024-01-31" and return the given time on that day.
+fn parse_date(date: &str, time: NaiveTime) -> Option<(NaiveDate, DateTime<Local>)> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+    let datetime = date.and_time(time).and_local_timezone(Local).earliest()?;
+    Some((date, datetime))
+}
+
+impl Progress {
+    /// Save a report and show where it went.
+    fn save_report(&mut self, name: &str, contents: String) {
+        let filename = report::report_filename(name, self.report_format);
+        self.save_file(&filename, contents);
+    }
+
+    /// Save an exported file and show where it went.
+    fn save_file(&mut self, filename: &str, contents: String) {
+        self.report_status = match export::save_file(filename, contents) {
+            Ok(location) => format!("Saved: {}", location),
+            Err(e) => e,
+        };
+    }
+
+    /// Create a report over all sessions in a date range.
+    fn reports(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        ui.horizontal(|ui| {
+            ui.label("Format");
+            for format in [ReportFormat::Html, ReportFormat::Markdown] {
+                ui.selectable_value(&mut self.report_format, format, format.to_string());
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("From");
+            ui.add(egui::TextEdit::singleline(&mut self.report_from).desired_width(80.));
+            ui.label("to");
+            ui.add(egui::TextEdit::singleline(&mut self.report_to).desired_width(80.));
+
+            let from = parse_date(&self.report_from, NaiveTime::MIN);
+            let to = parse_date(
+                &self.report_to,
+                NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default(),
+            );
+            match (from, to) {
+                (Some((from_date, from)), Some((to_date, to))) => {
+                    let records = appdata.history.between(from, to);
+                    let button = ui.add_enabled(
+                        !records.is_empty(),
+                        egui::Button::new(format!("Save report ({} sessions)", records.len())),
+                    );
+                    if button.clicked() {
+                        let contents = report::history_report(
+                            &records,
+                            from_date,
+                            to_date,
+                            self.report_format,
+                        );
+                        self.save_report(&format!("{}_{}", from_date, to_date), contents);
+                    }
+                }
+                _ => {
+                    ui.colored_label(Color32::RED, "Enter dates as YYYY-MM-DD.");
+                }
+            }
+        });
+
+        // Raw data for research
+        ui.horizontal(|ui| {
+            ui.label("Raw data of all trials of all profiles:");
+            let today = Local::now().date_naive();
+            if ui.button("Export CSV").clicked() {
+                let rows = export::trial_rows(appdata.history.all_records());
+                let filename = format!("perhabs_trials_{}.csv", today);
+                self.save_file(&filename, export::to_csv(&rows));
+            }
+            if ui.button("Export JSON").clicked() {
+                let rows = export::trial_rows(appdata.history.all_records());
+                let filename = format!("perhabs_trials_{}.json", today);
+                match export::to_json(&rows) {
+                    Ok(json) => self.save_file(&filename, json),
+                    Err(e) => self.report_status = e,
+                }
+            }
+        });
+
+        if !self.report_status.is_empty() {
+            ui.label(&self.report_status);
+        }
+    }
+
+    /// List all given sessions, each with a button to save a report of that session.
+    fn sessions(&mut self, ui: &mut egui::Ui, records: &[&SessionRecord]) {
+        egui::Grid::new("progress_sessions")
+            .striped(true)
+            .show(ui, |ui| {
+                for record in records.iter().rev() {
+                    let date = record.start_time.format("%Y-%m-%d %H:%M").to_string();
+                    ui.label(&date);
+                    ui.label(&record.config);
+                    ui.label(format!("{:.0}%", record.score * 100.));
+                    if ui.button("Save report").clicked() {
+                        let contents = report::session_report(record, self.report_format);
+                        self.save_report(&format!("{}_{}", record.exercise, date), contents);
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Plot a single measure for all given sessions. Sessions are plotted in order,
+    /// so the x axis shows the session number.
+    fn plot(&self, ui: &mut egui::Ui, records: &[&SessionRecord], measure: Measure) {
+        let points: Vec<[f64; 2]> = records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| [i as f64 + 1., measure.value(record)])
+            .collect();
+
+        ui.label(measure.to_string());
+        Plot::new(format!("progress_plot_{}", measure))
+            .height(120.)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.)
+            .x_axis_label("Session")
+            .label_formatter(move |_, value| {
+                let index = value.x.round() as usize;
+                format!("Session {}\n{:.1}", index, value.y)
+            })
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::from(points.clone())));
+                plot_ui.points(Points::new(PlotPoints::from(points)).radius(3.));
+            });
+    }
+}
+
+impl AppWin for Progress {
+    fn name(&self) -> &'static str {
+        "\u{1F4C8} Progress"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool, appdata: &AppData, tts: &mut Tts) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_size(vec2(500., 500.))
+            .show(ctx, |ui| self.ui(ui, appdata, tts));
+    }
+}
+
+impl View for Progress {
+    fn ui(&mut self, ui: &mut egui::Ui, appdata: &AppData, _: &mut Tts) {
+        let exercises = appdata.history.exercises();
+        if exercises.is_empty() {
+            ui.label("No sessions yet. Finish an exercise to start tracking your progress.");
+            return;
+        }
+
+        ui.collapsing("Reports", |ui| self.reports(ui, appdata));
+        ui.separator();
+
+        // Select the exercise to show
+        let selected_text = match &self.selected_exercise {
+            Some(name) => name.as_str(),
+            None => "Select an exercise",
+        };
+        egui::ComboBox::from_label("Exercise")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                for name in &exercises {
+                    ui.selectable_value(&mut self.selected_exercise, Some(name.to_string()), *name);
+                }
+            });
+        ui.separator();
+
+        let name = match &self.selected_exercise {
+            Some(name) => name,
+            None => return,
+        };
+        let records = appdata.history.for_exercise(name);
+        if let (Some(first), Some(last)) = (records.first(), records.last()) {
+            ui.label(format!(
+                "{} sessions from {} to {}.",
+                records.len(),
+                first.start_time.format("%Y-%m-%d"),
+                last.start_time.format("%Y-%m-%d")
+            ));
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for measure in [Measure::Score, Measure::Reps, Measure::SecsPerRep] {
+                self.plot(ui, &records, measure);
+            }
+            ui.collapsing("Sessions", |ui| self.sessions(ui, &records));
+        });
+    }
+}
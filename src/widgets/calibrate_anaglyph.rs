@@ -1,20 +1,24 @@
 use crate::shared::AnaglyphColor;
-use egui::{emath, epaint::PathShape, pos2, vec2, Frame, Margin, Rect, Stroke};
+use egui::{emath, epaint::PathShape, pos2, vec2, Color32, Frame, Margin, Rect, Stroke};
 
 /// Shows a menu to calibrate the colors used in the anaglyph painting.
 /// Different glasses for viewing anaglyphs exist, user must be able to
-/// set colors for optimal effect.
-/// TODO: there is currently no option to permanently save calibration data.
+/// set colors for optimal effect. The crosstalk check confirms that each filter
+/// blocks the color meant for the other eye.
 pub fn calibrate(ui: &mut egui::Ui, color: &mut AnaglyphColor, open: &mut bool) {
     ui.vertical(|ui| {
             ui.label("Calibrate the colors for your anaglyph glasses so each color is clearly visible to one eye, but hardly visible to the other. When properly calibrated the two diamonds may appear as one when seen through the glasses.");
             ui.separator();
             ui.horizontal(|ui| {
                 ui.label("Left eye");
-                ui.color_edit_button_srgba(&mut color.calibration_left);
+                if ui.color_edit_button_srgba(&mut color.calibration_left).changed() {
+                    color.reset_check();
+                }
                 ui.add_space(ui.available_width() / 3.);
 
-                ui.color_edit_button_srgba(&mut color.calibration_right);
+                if ui.color_edit_button_srgba(&mut color.calibration_right).changed() {
+                    color.reset_check();
+                }
                 ui.label("Right eye");
             });
 
@@ -75,6 +79,8 @@ pub fn calibrate(ui: &mut egui::Ui, color: &mut AnaglyphColor, open: &mut bool)
 
                 });
 
+            crosstalk_check(ui, color);
+
             ui.horizontal(|ui| {
                 if ui.button("Swap").clicked() {
                     color.swap_calibration_colors();
@@ -99,3 +105,43 @@ pub fn calibrate(ui: &mut egui::Ui, color: &mut AnaglyphColor, open: &mut bool)
 
         });
 }
+
+/// Shows one pattern per eye: a circle in the left eye color and a square in the right
+/// eye color. Looking with one eye at a time, the user confirms that each eye sees
+/// only its own pattern, i.e. the filters fully block the other color.
+fn crosstalk_check(ui: &mut egui::Ui, color: &mut AnaglyphColor) {
+    ui.separator();
+    ui.heading("Crosstalk check");
+    ui.label("Close one eye at a time. Each eye should see its own pattern only: if you can still see the other pattern, adjust the colors until it disappears.");
+
+    Frame::dark_canvas(ui.style()).show(ui, |ui| {
+        let (_id, rect) = ui.allocate_space(vec2(500., 150.));
+        let size = rect.height() * 0.6;
+        let left_center = pos2(rect.left() + rect.width() / 3., rect.center().y);
+        let right_center = pos2(rect.left() + rect.width() * 2. / 3., rect.center().y);
+
+        ui.painter()
+            .circle_filled(left_center, size / 2., color.calibration_left);
+        ui.painter().rect_filled(
+            Rect::from_center_size(right_center, vec2(size, size)),
+            0.,
+            color.calibration_right,
+        );
+    });
+
+    ui.checkbox(
+        &mut color.check_left,
+        "With only my left eye open, I see the circle but not the square.",
+    );
+    ui.checkbox(
+        &mut color.check_right,
+        "With only my right eye open, I see the square but not the circle.",
+    );
+    match color.check_left && color.check_right {
+        true => ui.colored_label(Color32::GREEN, "Crosstalk check passed."),
+        false => ui.colored_label(
+            Color32::from_rgb(255, 165, 0),
+            "Crosstalk check not done for these colors.",
+        ),
+    };
+}
@@ -1,6 +1,8 @@
 use chrono::Duration;
 use egui::*;
 
+use crate::shared::TrialTiming;
+
 use super::{
     circle_mutable::{circle_mut_duration, circle_mut_integer},
     circle_with_data,
@@ -47,6 +49,137 @@ pub fn post_eval_widgets(
     });
 }
 
+/// Review the reaction times of an evaluation. Takes the mean, median and variance
+/// of the reaction times in milliseconds; shows the variance as a standard deviation.
+pub fn reaction_time_widgets(
+    ui: &mut egui::Ui,
+    mean: Option<f32>,
+    median: Option<f32>,
+    variance: Option<f32>,
+) {
+    let (Some(mean), Some(median), Some(variance)) = (mean, median, variance) else {
+        ui.label("No reaction times recorded.");
+        return;
+    };
+
+    ui.horizontal(|ui| {
+        circle_with_data(
+            ui,
+            &format!("{:.0}", mean),
+            &String::from("Mean RT (ms)"),
+            100.,
+            Color32::BLUE,
+        );
+        circle_with_data(
+            ui,
+            &format!("{:.0}", median),
+            &String::from("Median RT (ms)"),
+            100.,
+            Color32::BLUE,
+        );
+        circle_with_data(
+            ui,
+            &format!("±{:.0}", variance.sqrt()),
+            &String::from("RT spread (ms)"),
+            100.,
+            Color32::BLUE,
+        );
+    });
+}
+
+/// Review a session trial by trial. Shows a scrollable table with a row per trial:
+/// a thumbnail of the stimulus and response, whether the response was correct
+/// and the reaction time.
+///
+/// `paint` draws a trial on its thumbnail. The transform maps coordinates from 0 to 1
+/// vertically and from 0 to the aspect ratio of the thumbnail horizontally.
+pub fn trial_review<T>(
+    ui: &mut egui::Ui,
+    thumbnail_size: Vec2,
+    trials: &[T],
+    scores: &[f32],
+    timings: &[TrialTiming],
+    mut paint: impl FnMut(&Painter, emath::RectTransform, &T),
+) {
+    ui.collapsing("Review trials", |ui| {
+        if trials.is_empty() {
+            ui.label("No trials recorded.");
+            return;
+        }
+
+        ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+            Grid::new("trial_review")
+                .striped(true)
+                .num_columns(4)
+                .show(ui, |ui| {
+                    ui.strong("#");
+                    ui.strong("Stimulus and response");
+                    ui.strong("Correct");
+                    ui.strong("Reaction time");
+                    ui.end_row();
+
+                    for (i, trial) in trials.iter().enumerate() {
+                        ui.label(format!("{}", i + 1));
+
+                        // Paint the trial on a thumbnail
+                        let (rect, _) = ui.allocate_exact_size(thumbnail_size, Sense::hover());
+                        let painter = ui.painter_at(rect);
+                        painter.rect_filled(rect, 3., ui.visuals().extreme_bg_color);
+                        let aspect = rect.width() / rect.height();
+                        let to_screen = emath::RectTransform::from_to(
+                            Rect::from_x_y_ranges(0.0..=aspect, 0.0..=1.0),
+                            rect,
+                        );
+                        paint(&painter, to_screen, trial);
+
+                        match scores.get(i) {
+                            Some(score) if *score >= 1. => {
+                                ui.colored_label(Color32::GREEN, "\u{2714}")
+                            }
+                            Some(score) if *score <= 0. => {
+                                ui.colored_label(Color32::RED, "\u{2716}")
+                            }
+                            Some(score) => ui.label(format!("{:.0}%", score * 100.)),
+                            None => ui.label("-"),
+                        };
+
+                        match timings.get(i).and_then(|timing| timing.reaction_time()) {
+                            Some(rt) => ui.label(format!("{} ms", rt.num_milliseconds())),
+                            None => ui.label("-"),
+                        };
+                        ui.end_row();
+                    }
+                });
+        });
+    });
+}
+
+/// Show the seed of a finished session, so the same session can be replayed later.
+pub fn seed_label(ui: &mut egui::Ui, seed: u64) {
+    ui.label(format!("Session seed: {}", seed))
+        .on_hover_text("Enter this seed before starting a session to repeat the same stimuli.");
+}
+
+/// Optionally set a seed to replay the stimuli of an earlier session.
+pub fn seed_widgets(ui: &mut egui::Ui, replay_seed: &mut Option<u64>) {
+    ui.horizontal(|ui| {
+        let mut replay = replay_seed.is_some();
+        if ui
+            .checkbox(&mut replay, "Replay a session with seed")
+            .changed()
+        {
+            *replay_seed = match replay {
+                true => Some(0),
+                false => None,
+            };
+        }
+        if let Some(seed) = replay_seed {
+            ui.add(DragValue::new(seed).clamp_range(0..=u32::MAX));
+        }
+    });
+    ui.separator();
+}
+
 /// A set of widgets to configure the evalation parameters.
 ///
 /// # Example
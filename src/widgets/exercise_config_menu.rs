@@ -8,13 +8,14 @@ use crate::shared::asset_loader::exercise_config::ExerciseConfig;
 /// To use, define T using turbofish (::<>) and load a vec containing exercise configs.
 /// When a button is clicked, it returns a reference to a specific config from this vec.
 /// The reference is wrapped in Option, so the function returns None when not clicked.
+/// The config named `preferred`, e.g. the one preferred by the active profile, is highlighted.
 ///
 /// ## Example
 /// ```ignore
 /// if let Some(list_of_configs) =
-///     exercise_config_menu::<MyConfig>(&mut ui, &config.some_exercise, 3)
+///     exercise_config_menu::<MyConfig>(&mut ui, &config.some_exercise, 3, None)
 /// {
-///     if let Some(config) = config_menu::<MyConfig>(ui, &config.some_exercise, 3)
+///     if let Some(config) = config_menu::<MyConfig>(ui, &config.some_exercise, 3, None)
 ///     {
 ///         // Use the selected config however you want.
 ///         my_exercise_starter_function(config)
@@ -25,6 +26,7 @@ pub fn exercise_config_menu<'a, T>(
     ui: &mut egui::Ui,
     config: &'a Vec<T>,
     num_cols: usize,
+    preferred: Option<&str>,
 ) -> Option<&'a T>
 where
     T: ExerciseConfig,
@@ -46,7 +48,13 @@ where
             let item_index_end = colnr * bin_size + bin_size;
             for i in item_index_start..item_index_end {
                 if let Some(exercise) = config.get(i) {
-                    if menu_button(&mut col[colnr], None, None, exercise.name(), "").clicked() {
+                    let override_color = match preferred == Some(exercise.name()) {
+                        true => Some(col[colnr].visuals().selection.bg_fill),
+                        false => None,
+                    };
+                    if menu_button(&mut col[colnr], None, override_color, exercise.name(), "")
+                        .clicked()
+                    {
                         return_val = Some(exercise);
                     };
                 };
@@ -1,5 +1,9 @@
+mod program;
 mod sessionman;
+mod task_switch;
 mod windowman;
 
+pub use self::program::*;
 pub use self::sessionman::*;
+pub use self::task_switch::*;
 pub use self::windowman::*;
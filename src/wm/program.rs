@@ -0,0 +1,258 @@
+use chrono::{DateTime, Duration, Local};
+use egui::{vec2, Align2};
+
+use crate::{
+    shared::{
+        asset_loader::programs::{Program, ProgramStep},
+        AppData, SessionRecord, Timer,
+    },
+    widgets,
+};
+
+/// How long the transition between two exercises takes, unless the user starts
+/// the next exercise sooner.
+const TRANSITION_SECS: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgramStage {
+    /// Announcing the next exercise.
+    Transition,
+    /// Running an exercise.
+    Exercise,
+    /// Showing the combined report.
+    Report,
+}
+
+/// What the user chose on the transition screen.
+pub enum TransitionAction {
+    Start,
+    Skip,
+    Stop,
+}
+
+/// A program that is being run: its exercises are shown one after another,
+/// with a short transition in between and a combined report at the end.
+pub struct ProgramRun {
+    pub program: Program,
+    step: usize,
+    stage: ProgramStage,
+    start_time: DateTime<Local>,
+    transition_timer: Timer,
+    /// Has the current exercise shown its results?
+    pub step_finished: bool,
+    /// The exercises that were started and when, so the report only shows their sessions.
+    started: Vec<(String, DateTime<Local>)>,
+}
+
+impl ProgramRun {
+    pub fn new(program: Program) -> Self {
+        let mut run = Self {
+            program,
+            step: 0,
+            stage: ProgramStage::Transition,
+            start_time: Local::now(),
+            transition_timer: Timer::new(),
+            step_finished: false,
+            started: vec![],
+        };
+        run.start_transition();
+        run
+    }
+
+    pub fn stage(&self) -> ProgramStage {
+        self.stage
+    }
+
+    /// The exercise that is announced or running.
+    pub fn current_step(&self) -> Option<&ProgramStep> {
+        self.program.steps.get(self.step)
+    }
+
+    /// Is the transition to the next exercise over?
+    pub fn transition_finished(&self) -> bool {
+        self.stage == ProgramStage::Transition && self.transition_timer.is_finished()
+    }
+
+    /// The current exercise has started.
+    pub fn start_exercise(&mut self) {
+        if let Some(step) = self.current_step() {
+            self.started.push((step.exercise.to_owned(), Local::now()));
+        }
+        self.stage = ProgramStage::Exercise;
+        self.step_finished = false;
+    }
+
+    /// Pause the transition to the next exercise.
+    pub fn pause(&mut self) {
+        self.transition_timer.pause();
+    }
+
+    /// Resume the transition to the next exercise.
+    pub fn resume(&mut self) {
+        self.transition_timer.resume();
+    }
+
+    /// Move on to the next exercise, or to the report after the last exercise.
+    pub fn next_step(&mut self) {
+        self.step += 1;
+        self.step_finished = false;
+        match self.step < self.program.steps.len() {
+            true => self.start_transition(),
+            false => self.stage = ProgramStage::Report,
+        }
+    }
+
+    fn start_transition(&mut self) {
+        self.stage = ProgramStage::Transition;
+        self.transition_timer
+            .set(Duration::try_seconds(TRANSITION_SECS).unwrap_or_default());
+    }
+
+    /// The sessions of the program's exercises. Each started exercise adds the
+    /// first session of that exercise that finished before the next one started.
+    fn records<'a>(&self, appdata: &'a AppData) -> Vec<&'a SessionRecord> {
+        let history = appdata.history.between(self.start_time, Local::now());
+        let mut records = vec![];
+        for (i, (exercise, started)) in self.started.iter().enumerate() {
+            let until = self.started.get(i + 1).map(|(_, time)| *time);
+            let record = history.iter().find(|r| {
+                &r.exercise == exercise
+                    && r.end_time >= *started
+                    && until.is_none_or(|until| r.end_time <= until)
+            });
+            if let Some(record) = record {
+                records.push(*record);
+            }
+        }
+        records
+    }
+
+    // ***********
+    // UI
+    // ***********
+
+    /// A window anchored to the top of the screen, like the exercise menus.
+    pub fn window(&self) -> egui::Window<'static> {
+        egui::Window::new(self.program.name.to_owned())
+            .anchor(Align2::CENTER_TOP, vec2(0., 100.))
+            .fixed_size(vec2(400., 300.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false)
+    }
+
+    /// Announce the next exercise.
+    pub fn transition_ui(&self, ui: &mut egui::Ui) -> Option<TransitionAction> {
+        let mut action = None;
+        let step = match self.current_step() {
+            Some(step) => step,
+            None => return Some(TransitionAction::Stop),
+        };
+
+        ui.ctx()
+            .request_repaint_after(std::time::Duration::from_millis(100));
+        ui.label(format!(
+            "Exercise {} of {}",
+            self.step + 1,
+            self.program.steps.len()
+        ));
+        ui.heading(&step.exercise);
+        ui.label(format!(
+            "{}, {} repetitions or {}:{:02} minutes.",
+            step.config,
+            step.reps,
+            step.duration_secs / 60,
+            step.duration_secs % 60
+        ));
+        ui.add_space(10.);
+        ui.label(format!(
+            "Starting in {} seconds.",
+            self.transition_timer.remaining().num_seconds() + 1
+        ));
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("Start now").clicked() {
+                action = Some(TransitionAction::Start);
+            }
+            if ui.button("Skip").clicked() {
+                action = Some(TransitionAction::Skip);
+            }
+            if ui.button("Stop program").clicked() {
+                action = Some(TransitionAction::Stop);
+            }
+        });
+
+        action
+    }
+
+    /// Offer to continue with the next exercise once an exercise shows its results.
+    /// Returns true when clicked.
+    pub fn continue_ui(&self, ctx: &egui::Context) -> bool {
+        let mut clicked = false;
+        let label = match self.step + 1 < self.program.steps.len() {
+            true => "Next exercise",
+            false => "Show report",
+        };
+        egui::Window::new(self.program.name.to_owned())
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-20., -20.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if ui.button(label).clicked() {
+                    clicked = true;
+                }
+            });
+        clicked
+    }
+
+    /// The combined report of all exercises in the program. Returns true when closed.
+    pub fn report_ui(&self, ui: &mut egui::Ui, appdata: &AppData) -> bool {
+        let records = self.records(appdata);
+        if records.is_empty() {
+            ui.label("No exercises were finished.");
+            return ui.button("Close").clicked();
+        }
+
+        // Totals
+        let reps: usize = records.iter().map(|r| r.reps_done).sum();
+        let duration = records
+            .iter()
+            .fold(Duration::zero(), |acc, r| acc + r.duration());
+        let score = records.iter().map(|r| r.score).sum::<f32>() / records.len() as f32;
+        widgets::evaluation::post_eval_widgets(
+            ui,
+            score,
+            reps,
+            format!(
+                "{}:{:02}",
+                duration.num_minutes(),
+                duration.num_seconds() % 60
+            ),
+        );
+        ui.separator();
+
+        // One row per exercise
+        egui::Grid::new("program_report")
+            .striped(true)
+            .num_columns(4)
+            .show(ui, |ui| {
+                ui.strong("Exercise");
+                ui.strong("Config");
+                ui.strong("Reps");
+                ui.strong("Score");
+                ui.end_row();
+                for record in &records {
+                    ui.label(&record.exercise);
+                    ui.label(&record.config);
+                    ui.label(format!("{} / {}", record.reps_done, record.repetitions));
+                    ui.label(format!("{:.0}%", record.score * 100.));
+                    ui.end_row();
+                }
+            });
+        ui.separator();
+
+        ui.button("Close").clicked()
+    }
+}
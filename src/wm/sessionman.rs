@@ -2,14 +2,20 @@ use std::rc::Rc;
 
 use crate::{
     exercises::*,
-    shared::AppData,
+    shared::{asset_loader::programs::Program, AppData},
     widgets::{self, menu_button},
 };
 
+use chrono::Duration;
 use egui::vec2;
+use serde::{Deserialize, Serialize};
 use tts::Tts;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use super::{
+    ProgramRun, ProgramStage, SwitchRun, SwitchSetup, SwitchStage, TransitionAction, SWITCH_CUE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum ExerciseType {
     Cognitive,
     Visual,
@@ -28,11 +34,20 @@ impl std::fmt::Display for ExerciseType {
 
 /// Stores all available exercises (sessions). In order to only display one, store its name in open
 /// and only display the session matching that static str.
+/// A running program opens its exercises one after another. When task switching,
+/// two exercises run at the same time and the open session is the one that has control.
 pub struct SessionManager {
     pub sessions: Vec<Box<dyn Exercise>>,
     pub selected_types: Vec<ExerciseType>,
     pub selected_sessions: Vec<usize>,
     pub open_session: Option<&'static str>,
+    pub program: Option<ProgramRun>,
+    pub switch_setup: SwitchSetup,
+    pub task_switch: Option<SwitchRun>,
+    /// Is the open session paused?
+    pub paused: bool,
+    /// Did the window have focus in the previous frame?
+    was_focused: bool,
 }
 
 impl Default for SessionManager {
@@ -40,8 +55,13 @@ impl Default for SessionManager {
         let sessions: Vec<Box<dyn Exercise>> = vec![
             Box::new(CogNumbers::default()),
             Box::new(CogWords::default()),
+            Box::new(WordBreakdown::default()),
             Box::new(NumSeq::default()),
             Box::new(EpisodicMemory::default()),
+            Box::new(Scattergories::default()),
+            Box::new(Lingo::default()),
+            Box::new(MultiTasker::default()),
+            Box::new(NBack::default()),
             Box::new(SpatialDrawing::default()),
             Box::new(Vergence::default()),
             Box::new(DepthPerception::default()),
@@ -58,11 +78,32 @@ impl Default for SessionManager {
             sessions,
             selected_types: Vec::new(),
             open_session: None,
+            program: None,
+            switch_setup: SwitchSetup::default(),
+            task_switch: None,
+            paused: false,
+            was_focused: false,
         }
     }
 }
 
 impl SessionManager {
+    /// Select the exercises that match the selected types.
+    pub fn update_selected_sessions(&mut self) {
+        self.selected_sessions = self
+            .sessions
+            .iter()
+            .enumerate()
+            .filter(|(_, session)| {
+                session
+                    .excercise_type()
+                    .iter()
+                    .any(|t| self.selected_types.contains(t))
+            })
+            .map(|(index, _)| index)
+            .collect();
+    }
+
     fn exercise_type_selector(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             for exercise_type in [
@@ -89,25 +130,42 @@ impl SessionManager {
                     } else {
                         self.selected_types.push(exercise_type);
                     }
-                    // Update selected exercises based on selected types
-                    self.selected_sessions = self
-                        .sessions
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, session)| {
-                            session
-                                .excercise_type()
-                                .iter()
-                                .any(|t| self.selected_types.contains(t))
-                        })
-                        .map(|(index, _)| index)
-                        .collect();
+                    self.update_selected_sessions();
                 }
             }
         });
     }
 
-    pub fn exercise_buttons(&mut self, ui: &mut egui::Ui) {
+    /// Buttons to start a program.
+    fn program_buttons(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        let programs = match &appdata.programs {
+            Some(programs) if !programs.programs.is_empty() => &programs.programs,
+            _ => return,
+        };
+        ui.collapsing("Programs", |ui| {
+            for program in programs {
+                if menu_button(ui, None, None, &program.name, &program.description).clicked() {
+                    self.start_program(program.to_owned());
+                };
+            }
+        });
+    }
+
+    /// Set up two exercises to switch between.
+    fn task_switch_buttons(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        let names: Vec<&'static str> = self.sessions.iter().map(|s| s.name()).collect();
+        let mut start = false;
+        ui.collapsing("Task switching", |ui| {
+            start = self.switch_setup.ui(ui, &names);
+        });
+        if start {
+            self.start_task_switch(appdata);
+        }
+    }
+
+    pub fn exercise_buttons(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
+        self.program_buttons(ui, appdata);
+        self.task_switch_buttons(ui, appdata);
         for session in &self.sessions {
             if menu_button(ui, None, None, session.name(), session.description()).clicked() {
                 self.open_session = Some(session.name());
@@ -115,9 +173,11 @@ impl SessionManager {
         }
     }
 
-    pub fn exercise_buttons_cols(&mut self, ui: &mut egui::Ui) {
+    pub fn exercise_buttons_cols(&mut self, ui: &mut egui::Ui, appdata: &AppData) {
         // Display the exercise type selector first at the top.
         self.exercise_type_selector(ui);
+        self.program_buttons(ui, appdata);
+        self.task_switch_buttons(ui, appdata);
 
         let buttons_total: f32 = self.selected_sessions.len() as f32;
         let col_1_range = buttons_total - (buttons_total / 2.).floor();
@@ -166,7 +226,359 @@ impl SessionManager {
         });
     }
 
+    /// Is a session, program or task switch open?
+    pub fn is_active(&self) -> bool {
+        self.open_session.is_some() || self.program.is_some() || self.task_switch.is_some()
+    }
+
+    /// Close the open session, program and task switch and reset all sessions.
+    pub fn reset_all(&mut self) {
+        self.open_session = None;
+        self.program = None;
+        self.task_switch = None;
+        self.paused = false;
+        for session in &mut self.sessions {
+            session.reset();
+        }
+    }
+
+    /// Quit the open session, program or task switch. Only the exercises that
+    /// took part are reset, including the paused exercise of a task switch.
+    pub fn quit(&mut self) {
+        let mut names: Vec<&'static str> = self.open_session.into_iter().collect();
+        if let Some(run) = &self.task_switch {
+            names.extend(run.exercises);
+        }
+        self.open_session = None;
+        self.program = None;
+        self.task_switch = None;
+        self.paused = false;
+        for session in &mut self.sessions {
+            if names.contains(&session.name()) {
+                session.reset();
+            }
+        }
+    }
+
+    /// Pause the open session or program. Its timers stop and its stimulus is hidden.
+    pub fn pause(&mut self) {
+        if self.paused || !self.is_active() {
+            return;
+        }
+        self.paused = true;
+        if let Some(run) = &mut self.program {
+            run.pause();
+        }
+        if let Some(run) = &mut self.task_switch {
+            run.pause();
+        }
+        if let Some(session) = self.open_session_mut() {
+            session.pause();
+        }
+    }
+
+    /// Resume the paused session or program.
+    pub fn resume(&mut self) {
+        if !self.paused {
+            return;
+        }
+        self.paused = false;
+        if let Some(run) = &mut self.program {
+            run.resume();
+        }
+        if let Some(run) = &mut self.task_switch {
+            run.resume();
+        }
+        if let Some(session) = self.open_session_mut() {
+            session.resume();
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.paused {
+            true => self.resume(),
+            false => self.pause(),
+        }
+    }
+
+    fn open_session_mut(&mut self) -> Option<&mut Box<dyn Exercise>> {
+        let name = self.open_session?;
+        self.sessions.iter_mut().find(|s| s.name() == name)
+    }
+
+    /// Pause with the P key, resume with the P key, and pause when the window loses focus.
+    fn pause_controls(&mut self, ctx: &egui::Context) {
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::P)) {
+            self.toggle_pause();
+        }
+        let focused = ctx.input(|i| i.focused);
+        if self.was_focused && !focused {
+            self.pause();
+        }
+        self.was_focused = focused;
+    }
+
+    /// Covers the whole screen, so no stimulus is visible while paused.
+    fn pause_screen(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.);
+                ui.heading("Paused");
+                ui.label("Press P or click resume to continue.");
+                ui.add_space(10.);
+                if ui.button("\u{25B6} Resume").clicked() {
+                    self.resume();
+                }
+            });
+        });
+    }
+
+    /// Start running a program from its first exercise.
+    pub fn start_program(&mut self, program: Program) {
+        self.reset_all();
+        self.program = Some(ProgramRun::new(program));
+    }
+
+    /// Start the current exercise of the running program. Exercises that can't
+    /// be started are skipped.
+    fn start_program_step(&mut self, appdata: &AppData) {
+        let Some(run) = &mut self.program else {
+            return;
+        };
+        let Some(step) = run.current_step() else {
+            run.next_step();
+            return;
+        };
+        let Some(session) = self.sessions.iter_mut().find(|s| s.name() == step.exercise) else {
+            let _ = appdata.error_tx.send(format!(
+                "Program: there is no exercise named {}.",
+                step.exercise
+            ));
+            run.next_step();
+            return;
+        };
+
+        session.reset();
+        let duration = Duration::try_seconds(step.duration_secs).unwrap_or_default();
+        match session.start_with(appdata, &step.config, duration, step.reps) {
+            Ok(_) => {
+                self.open_session = Some(session.name());
+                run.start_exercise();
+            }
+            Err(e) => {
+                let _ = appdata.error_tx.send(format!("Program: {}", e));
+                run.next_step();
+            }
+        }
+    }
+
+    /// Show the running program: the transition to the next exercise,
+    /// the exercise itself or the final report.
+    fn program_show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        let Some(run) = &mut self.program else {
+            return;
+        };
+
+        match run.stage() {
+            ProgramStage::Transition => {
+                let mut action = None;
+                run.window().show(ctx, |ui| action = run.transition_ui(ui));
+                if run.transition_finished() {
+                    action = Some(TransitionAction::Start);
+                }
+                match action {
+                    Some(TransitionAction::Start) => self.start_program_step(appdata),
+                    Some(TransitionAction::Skip) => run.next_step(),
+                    Some(TransitionAction::Stop) => self.program = None,
+                    None => (),
+                }
+            }
+            ProgramStage::Exercise => {
+                // Quitting the session stops the program.
+                let Some(name) = self.open_session else {
+                    self.program = None;
+                    return;
+                };
+                let Some(session) = self.sessions.iter_mut().find(|s| s.name() == name) else {
+                    return;
+                };
+                session.show(ctx, appdata, tts);
+
+                // Continue when asked to, or when the results screen is closed.
+                let next = match session.is_finished() {
+                    true => {
+                        run.step_finished = true;
+                        run.continue_ui(ctx)
+                    }
+                    false => run.step_finished,
+                };
+                if next {
+                    session.reset();
+                    self.open_session = None;
+                    run.next_step();
+                }
+            }
+            ProgramStage::Report => {
+                let mut close = false;
+                run.window()
+                    .show(ctx, |ui| close = run.report_ui(ui, appdata));
+                if close {
+                    self.program = None;
+                }
+            }
+        }
+    }
+
+    /// Start both exercises of the task switch setup. The second one waits,
+    /// paused, for the first switch.
+    pub fn start_task_switch(&mut self, appdata: &AppData) {
+        self.reset_all();
+        let setup = &self.switch_setup;
+        // Each exercise only runs while it has control, so gets about half the time.
+        let duration = Duration::try_seconds(setup.duration_secs / 2).unwrap_or_default();
+        for (name, config) in setup.exercises.iter().zip(&setup.configs) {
+            let Some(session) = self.sessions.iter_mut().find(|s| s.name() == *name) else {
+                let _ = appdata.error_tx.send(format!(
+                    "Task switching: there is no exercise named {}.",
+                    name
+                ));
+                self.reset_all();
+                return;
+            };
+            if let Err(e) = session.start_with(appdata, config, duration, setup.reps) {
+                let _ = appdata.error_tx.send(format!("Task switching: {}", e));
+                self.reset_all();
+                return;
+            }
+        }
+
+        let run = SwitchRun::new(setup);
+        if let Some(session) = self
+            .sessions
+            .iter_mut()
+            .find(|s| s.name() == run.inactive_exercise())
+        {
+            session.pause();
+        }
+        self.open_session = Some(run.active_exercise());
+        self.task_switch = Some(run);
+    }
+
+    /// Show the running task switch: the exercise that has control, followed by
+    /// the results of both exercises and the switch costs.
+    fn task_switch_show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        let Some(run) = &mut self.task_switch else {
+            return;
+        };
+
+        match run.stage() {
+            SwitchStage::Running => {
+                let (active, inactive) = (run.active_exercise(), run.inactive_exercise());
+                let can_switch = self
+                    .sessions
+                    .iter_mut()
+                    .find(|s| s.name() == inactive)
+                    .is_some_and(|s| !s.is_finished());
+
+                // The status bar goes first, so the exercise fills the rest of the screen.
+                let mut stop = false;
+                egui::TopBottomPanel::bottom("task_switch_status")
+                    .show(ctx, |ui| stop = run.status_ui(ui, can_switch));
+                if stop {
+                    self.reset_all();
+                    return;
+                }
+
+                let Some(session) = self.sessions.iter_mut().find(|s| s.name() == active) else {
+                    return;
+                };
+                session.show(ctx, appdata, tts);
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+
+                match (session.is_finished(), can_switch) {
+                    // Both are finished: measure the switch costs before the
+                    // results are closed and the exercises reset.
+                    (true, false) => {
+                        let scores = run.exercises.map(|name| {
+                            self.sessions
+                                .iter()
+                                .find(|s| s.name() == name)
+                                .map(|s| s.trial_scores())
+                                .unwrap_or_default()
+                        });
+                        run.finish(scores);
+                    }
+                    // Keep going with the other exercise until it is finished too.
+                    (true, true) => self.switch_exercise(tts),
+                    (false, true) if run.switch_due() => self.switch_exercise(tts),
+                    _ => (),
+                }
+            }
+            SwitchStage::Results(i) => {
+                let name = run.exercises[i];
+                self.open_session = Some(name);
+                let Some(session) = self.sessions.iter_mut().find(|s| s.name() == name) else {
+                    run.next_results();
+                    return;
+                };
+                session.show(ctx, appdata, tts);
+                // Continue when the results screen is closed.
+                if !session.is_finished() {
+                    session.reset();
+                    run.next_results();
+                }
+            }
+            SwitchStage::Report => {
+                let mut close = false;
+                run.window().show(ctx, |ui| close = run.report_ui(ui));
+                if close {
+                    self.reset_all();
+                }
+            }
+        }
+    }
+
+    /// Pause the exercise that has control, give control to the other one and
+    /// announce the switch.
+    fn switch_exercise(&mut self, tts: &mut Tts) {
+        let Some(run) = &mut self.task_switch else {
+            return;
+        };
+        let (from, to) = (run.active_exercise(), run.inactive_exercise());
+        for session in &mut self.sessions {
+            if session.name() == from {
+                session.pause();
+            }
+            if session.name() == to {
+                run.switch(session.trial_scores().len());
+                session.resume();
+            }
+        }
+        self.open_session = Some(to);
+
+        match tts.speak(SWITCH_CUE, true) {
+            Ok(_) => debug!("TTS: Switch spoken."),
+            Err(e) => warn!("TTS error: {:?}", e),
+        };
+    }
+
     pub fn session_show(&mut self, ctx: &egui::Context, appdata: &AppData, tts: &mut Tts) {
+        self.pause_controls(ctx);
+        if self.paused {
+            self.pause_screen(ctx);
+            return;
+        }
+
+        if self.program.is_some() {
+            self.program_show(ctx, appdata, tts);
+            return;
+        }
+
+        if self.task_switch.is_some() {
+            self.task_switch_show(ctx, appdata, tts);
+            return;
+        }
+
         // What is the currently open session?
         let name = match self.open_session {
             Some(name) => name,
@@ -201,6 +613,36 @@ pub trait Exercise {
     /// Shows the exercise session i.e. the exercise as it is being trained.
     fn session(&mut self, ui: &mut egui::Ui, appdata: &AppData, tts: &mut Tts);
 
+    /// Start a session with the named config and session length, e.g. as part of a program.
+    /// Exercises that can't be started this way return an error.
+    fn start_with(
+        &mut self,
+        _appdata: &AppData,
+        _config: &str,
+        _duration: Duration,
+        _reps: usize,
+    ) -> Result<(), String> {
+        Err(format!("{} can't be started from a program.", self.name()))
+    }
+
+    /// Is the session finished, i.e. showing its results?
+    fn is_finished(&mut self) -> bool {
+        false
+    }
+
+    /// Pause the session: stop the evaluation and any other timers.
+    /// Exercises without timers don't need to do anything.
+    fn pause(&mut self) {}
+
+    /// Resume a paused session.
+    fn resume(&mut self) {}
+
+    /// The score of each trial so far, used to measure the switch cost when
+    /// switching between exercises. Exercises without scored trials return none.
+    fn trial_scores(&self) -> Vec<f32> {
+        vec![]
+    }
+
     // To make sure we can clean up when quitting session.
     fn reset(&mut self);
 }
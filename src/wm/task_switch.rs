@@ -0,0 +1,348 @@
+use chrono::Duration;
+use egui::{vec2, Align2};
+use rand::prelude::*;
+
+use crate::shared::{stats, Timer};
+
+/// Spoken when the exercises switch.
+pub const SWITCH_CUE: &str = "Switch";
+
+/// The number of trials right after a switch that are affected by it.
+/// Later trials count as steady state.
+const SWITCH_WINDOW: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwitchSchedule {
+    /// Switch every `min_secs`.
+    Fixed,
+    /// Switch after a random time between `min_secs` and `max_secs`.
+    Random,
+}
+
+impl std::fmt::Display for SwitchSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwitchSchedule::Fixed => write!(f, "Fixed"),
+            SwitchSchedule::Random => write!(f, "Random"),
+        }
+    }
+}
+
+/// The task switching session as set up in the menu.
+pub struct SwitchSetup {
+    pub exercises: [&'static str; 2],
+    pub configs: [String; 2],
+    /// Length of the whole session. Each exercise gets about half of it.
+    pub duration_secs: i64,
+    /// Maximum repetitions of each exercise.
+    pub reps: usize,
+    pub schedule: SwitchSchedule,
+    pub min_secs: i64,
+    pub max_secs: i64,
+}
+
+impl Default for SwitchSetup {
+    fn default() -> Self {
+        Self {
+            exercises: ["Scanning (Saccades)", "Cognitive Numbers"],
+            configs: [String::from("Easy (big)"), String::from("5 numbers")],
+            duration_secs: 300,
+            reps: 60,
+            schedule: SwitchSchedule::Random,
+            min_secs: 15,
+            max_secs: 45,
+        }
+    }
+}
+
+impl SwitchSetup {
+    /// Pick the exercises and the schedule. Returns true when the session should start.
+    pub fn ui(&mut self, ui: &mut egui::Ui, names: &[&'static str]) -> bool {
+        ui.label("Two exercises take turns. A spoken cue tells you when to switch.");
+        egui::Grid::new("task_switch_setup")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for i in 0..2 {
+                    egui::ComboBox::from_id_source(("task_switch_exercise", i))
+                        .selected_text(self.exercises[i])
+                        .width(200.)
+                        .show_ui(ui, |ui| {
+                            for name in names {
+                                ui.selectable_value(&mut self.exercises[i], name, *name);
+                            }
+                        });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.configs[i])
+                            .hint_text("Config, e.g. Easy (big)"),
+                    );
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.label("Switch");
+            for schedule in [SwitchSchedule::Fixed, SwitchSchedule::Random] {
+                ui.selectable_value(&mut self.schedule, schedule, schedule.to_string());
+            }
+        });
+        match self.schedule {
+            SwitchSchedule::Fixed => {
+                ui.add(egui::Slider::new(&mut self.min_secs, 5..=120).text("seconds"));
+            }
+            SwitchSchedule::Random => {
+                ui.add(egui::Slider::new(&mut self.min_secs, 5..=120).text("seconds at least"));
+                ui.add(egui::Slider::new(&mut self.max_secs, 5..=120).text("seconds at most"));
+                self.max_secs = self.max_secs.max(self.min_secs);
+            }
+        }
+        ui.add(
+            egui::Slider::new(&mut self.duration_secs, 60..=1200)
+                .step_by(30.)
+                .text("seconds in total"),
+        );
+        ui.add(egui::Slider::new(&mut self.reps, 10..=200).text("repetitions per exercise"));
+
+        ui.add_enabled(
+            self.exercises[0] != self.exercises[1],
+            egui::Button::new("Start task switching"),
+        )
+        .clicked()
+    }
+}
+
+/// Scores of the trials right after a switch, compared to the other trials.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwitchCost {
+    pub after_switch: f32,
+    pub steady: f32,
+}
+
+impl SwitchCost {
+    /// How much the score drops right after a switch.
+    pub fn cost(&self) -> f32 {
+        self.steady - self.after_switch
+    }
+}
+
+/// Compare the scores of the trials within `SWITCH_WINDOW` of a switch with the
+/// other trials. `switch_points` holds the number of trials done at each switch.
+/// Returns None without trials on both sides.
+pub fn switch_cost(scores: &[f32], switch_points: &[usize]) -> Option<SwitchCost> {
+    let is_after_switch = |trial: usize| {
+        switch_points
+            .iter()
+            .any(|p| (*p..*p + SWITCH_WINDOW).contains(&trial))
+    };
+    let (after_switch, steady): (Vec<usize>, Vec<usize>) =
+        (0..scores.len()).partition(|trial| is_after_switch(*trial));
+    let scores = |trials: Vec<usize>| -> Vec<f32> { trials.iter().map(|t| scores[*t]).collect() };
+    Some(SwitchCost {
+        after_switch: stats::mean(&scores(after_switch))?,
+        steady: stats::mean(&scores(steady))?,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwitchStage {
+    /// The exercises take turns.
+    Running,
+    /// Showing the results of one of the exercises.
+    Results(usize),
+    /// Showing the switch costs.
+    Report,
+}
+
+/// Two exercises that are running at the same time. Only the active exercise is
+/// shown, the other one is paused until the next switch.
+pub struct SwitchRun {
+    pub exercises: [&'static str; 2],
+    schedule: SwitchSchedule,
+    min_secs: i64,
+    max_secs: i64,
+    active: usize,
+    stage: SwitchStage,
+    switch_timer: Timer,
+    /// For each exercise, the number of trials done whenever it was switched to.
+    switch_points: [Vec<usize>; 2],
+    costs: [Option<SwitchCost>; 2],
+}
+
+impl SwitchRun {
+    pub fn new(setup: &SwitchSetup) -> Self {
+        let mut run = Self {
+            exercises: setup.exercises,
+            schedule: setup.schedule,
+            min_secs: setup.min_secs,
+            max_secs: setup.max_secs.max(setup.min_secs),
+            active: 0,
+            stage: SwitchStage::Running,
+            switch_timer: Timer::new(),
+            switch_points: [vec![], vec![]],
+            costs: [None, None],
+        };
+        run.schedule_switch();
+        run
+    }
+
+    pub fn stage(&self) -> SwitchStage {
+        self.stage
+    }
+
+    /// The exercise that has control.
+    pub fn active_exercise(&self) -> &'static str {
+        self.exercises[self.active]
+    }
+
+    /// The exercise that waits for its turn.
+    pub fn inactive_exercise(&self) -> &'static str {
+        self.exercises[1 - self.active]
+    }
+
+    /// Is it time to switch?
+    pub fn switch_due(&self) -> bool {
+        self.stage == SwitchStage::Running && self.switch_timer.is_finished()
+    }
+
+    /// Hand control to the other exercise, which has done `trials_done` trials so far.
+    pub fn switch(&mut self, trials_done: usize) {
+        self.active = 1 - self.active;
+        self.switch_points[self.active].push(trials_done);
+        self.schedule_switch();
+    }
+
+    fn schedule_switch(&mut self) {
+        let secs = match self.schedule {
+            SwitchSchedule::Fixed => self.min_secs,
+            SwitchSchedule::Random => thread_rng().gen_range(self.min_secs..=self.max_secs),
+        };
+        self.switch_timer
+            .set(Duration::try_seconds(secs).unwrap_or_default());
+    }
+
+    /// Pause the switch schedule.
+    pub fn pause(&mut self) {
+        self.switch_timer.pause();
+    }
+
+    /// Resume the switch schedule.
+    pub fn resume(&mut self) {
+        self.switch_timer.resume();
+    }
+
+    /// Both exercises are finished: measure the switch costs from the trial
+    /// scores of each exercise and show their results.
+    pub fn finish(&mut self, scores: [Vec<f32>; 2]) {
+        for (i, scores) in scores.iter().enumerate() {
+            self.costs[i] = switch_cost(scores, &self.switch_points[i]);
+        }
+        self.stage = SwitchStage::Results(0);
+    }
+
+    /// The results of an exercise were closed: show the next ones or the report.
+    pub fn next_results(&mut self) {
+        self.stage = match self.stage {
+            SwitchStage::Results(0) => SwitchStage::Results(1),
+            _ => SwitchStage::Report,
+        };
+    }
+
+    // ***********
+    // UI
+    // ***********
+
+    /// Shows the time until the next switch. Returns true when the session should stop.
+    pub fn status_ui(&self, ui: &mut egui::Ui, can_switch: bool) -> bool {
+        let mut stop = false;
+        ui.horizontal(|ui| {
+            ui.label(format!("Task switching: {}", self.active_exercise()));
+            ui.separator();
+            match can_switch {
+                true => ui.label(format!(
+                    "Switching to {} in {} seconds.",
+                    self.inactive_exercise(),
+                    self.switch_timer.remaining().num_seconds() + 1
+                )),
+                false => ui.label(format!("{} is finished.", self.inactive_exercise())),
+            };
+            ui.separator();
+            if ui.button("Stop task switching").clicked() {
+                stop = true;
+            }
+        });
+        stop
+    }
+
+    /// A window anchored to the top of the screen, like the exercise menus.
+    pub fn window(&self) -> egui::Window<'static> {
+        egui::Window::new("Task switching")
+            .anchor(Align2::CENTER_TOP, vec2(0., 100.))
+            .fixed_size(vec2(400., 300.))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false)
+    }
+
+    /// The switch cost of both exercises. Returns true when closed.
+    pub fn report_ui(&self, ui: &mut egui::Ui) -> bool {
+        ui.label("The switch cost is how much your score drops right after a switch.");
+        ui.label(format!(
+            "The first {} trials after a switch are compared with the other trials.",
+            SWITCH_WINDOW
+        ));
+        ui.separator();
+
+        egui::Grid::new("task_switch_report")
+            .striped(true)
+            .num_columns(4)
+            .show(ui, |ui| {
+                ui.strong("Exercise");
+                ui.strong("After switch");
+                ui.strong("Steady");
+                ui.strong("Switch cost");
+                ui.end_row();
+                for (name, cost) in self.exercises.iter().zip(&self.costs) {
+                    ui.label(*name);
+                    match cost {
+                        Some(cost) => {
+                            ui.label(format!("{:.0}%", cost.after_switch * 100.));
+                            ui.label(format!("{:.0}%", cost.steady * 100.));
+                            ui.label(format!("{:.0}%", cost.cost() * 100.));
+                        }
+                        None => {
+                            ui.label("-");
+                            ui.label("-");
+                            ui.label("Not enough trials");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        ui.separator();
+
+        ui.button("Close").clicked()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_of_switching() {
+        // Switched to at the start and after trial 4: trials 0, 1, 4 and 5 follow a switch.
+        let scores = [0., 1., 1., 1., 0., 0., 1., 1.];
+        let cost = switch_cost(&scores, &[0, 4]).unwrap();
+        assert_eq!(cost.after_switch, 0.25);
+        assert_eq!(cost.steady, 1.);
+        assert_eq!(cost.cost(), 0.75);
+
+        // Overlapping windows count trials once.
+        let cost = switch_cost(&[0., 0., 0., 1.], &[0, 1]).unwrap();
+        assert_eq!(cost.after_switch, 0.);
+        assert_eq!(cost.steady, 1.);
+
+        // Without a switch, or without steady trials, there's nothing to compare.
+        assert_eq!(switch_cost(&scores, &[]), None);
+        assert_eq!(switch_cost(&[1., 0.], &[0]), None);
+    }
+}
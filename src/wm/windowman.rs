@@ -1,14 +1,13 @@
 #![warn(clippy::all)]
 use crate::{
     shared::AppData,
-    tools::{clock, debug_info, metronome, rand_timer},
+    tools::{breathing, clock, debug_info, metronome, progress, rand_timer},
 };
 use egui::{Context, Ui};
 use std::collections::BTreeSet;
 use tts::Tts;
 
 pub struct Windows {
-    #[cfg_attr(feature = "serde", serde(skip))]
     pub windows: Vec<Box<dyn AppWin>>,
     pub open: BTreeSet<String>,
 }
@@ -16,12 +15,12 @@ pub struct Windows {
 impl Default for Windows {
     fn default() -> Self {
         Self::from_windows(vec![
-            // Box::new(multitasker::MultiTasker::default()),
-            #[cfg(not(target_arch = "wasm32"))]
-            Box::new(rand_timer::RandTimer::default()), // WASM doesn't support threading
+            Box::new(rand_timer::RandTimer::default()),
             Box::new(clock::Clock::default()),
             Box::new(debug_info::DebugInfo::default()),
             Box::new(metronome::Metronome::default()),
+            Box::new(breathing::Breathing::default()),
+            Box::new(progress::Progress::default()),
         ])
     }
 }